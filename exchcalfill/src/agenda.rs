@@ -0,0 +1,90 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+use crate::model::{EventTime, ExistingEvent, NewEvent, start_of_day};
+
+
+/// Returns the start of the given date and the start of the following date in the given time zone.
+pub(crate) fn day_bounds(date: &NaiveDate, tz: &Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let next_date = date.checked_add_days(Days::new(1)).unwrap();
+    (start_of_day(*date, tz), start_of_day(next_date, tz))
+}
+
+/// Returns whether the event overlaps the half-open time range `[start, end)`.
+pub(crate) fn overlaps(event: &ExistingEvent, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+    event.start_time < end && start < event.end_time
+}
+
+fn format_time_on(date: &NaiveDate, tz: &Tz, time: &DateTime<Utc>) -> String {
+    let local_time = time.with_timezone(tz);
+    if local_time.date_naive() == *date {
+        local_time.format("%H:%M").to_string()
+    } else {
+        local_time.format("%m-%d %H:%M").to_string()
+    }
+}
+
+/// Outputs a compact agenda of the given events on the given date, with times in the given time
+/// zone.
+///
+/// If a candidate time range is given, events overlapping it are highlighted.
+pub(crate) fn print_agenda(date: &NaiveDate, tz: &Tz, events: &[ExistingEvent], candidate: Option<(DateTime<Utc>, DateTime<Utc>)>) {
+    if events.is_empty() {
+        println!("> nothing scheduled on {}", date.format("%Y-%m-%d"));
+        return;
+    }
+
+    let mut sorted_events: Vec<&ExistingEvent> = events.iter().collect();
+    sorted_events.sort_by_key(|e| (e.start_time, e.end_time));
+
+    println!("> agenda for {}:", date.format("%Y-%m-%d"));
+    for event in sorted_events {
+        let marker = match candidate {
+            Some((start, end)) if overlaps(event, start, end) => "!!",
+            _ => "  ",
+        };
+        let location = event.location.as_ref()
+            .map(|l| format!(" @ {}", l))
+            .unwrap_or_default();
+        println!(
+            ">{} {}-{} [{}] {}{}",
+            marker,
+            format_time_on(date, tz, &event.start_time),
+            format_time_on(date, tz, &event.end_time),
+            event.free_busy_status.as_exchange_str(),
+            event.title,
+            location,
+        );
+    }
+}
+
+/// Describes a new event on a single line, with times in the given time zone.
+pub(crate) fn describe_new_event(event: &NewEvent, tz: &Tz) -> String {
+    let time_description = match &event.time {
+        EventTime::AllDay { first_date, last_date, .. } if first_date == last_date => {
+            format!("{} all day", first_date.format("%Y-%m-%d"))
+        },
+        EventTime::AllDay { first_date, last_date, .. } => {
+            format!("{} to {} all day", first_date.format("%Y-%m-%d"), last_date.format("%Y-%m-%d"))
+        },
+        EventTime::Instants { start, end } => {
+            let start_date = start.with_timezone(tz).date_naive();
+            format!(
+                "{} {}-{}",
+                start_date.format("%Y-%m-%d"),
+                format_time_on(&start_date, tz, &start.to_utc()),
+                format_time_on(&start_date, tz, &end.to_utc()),
+            )
+        },
+    };
+    let location = event.location.as_ref()
+        .map(|l| format!(" @ {}", l))
+        .unwrap_or_default();
+    format!(
+        "{} [{}] {}{}",
+        time_description,
+        event.free_busy_status.as_exchange_str(),
+        event.title,
+        location,
+    )
+}
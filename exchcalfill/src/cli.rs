@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::model::ExistingEvent;
+use crate::spec::EventSpec;
+
+
+/// Quickly add many appointments to a Microsoft Exchange calendar.
+#[derive(Clone, Debug, Parser)]
+pub struct Cli {
+    /// The configuration file.
+    #[arg(long, global = true, default_value = "config.toml")]
+    pub config: PathBuf,
+
+    /// A profile from the configuration file whose settings override the top-level ones.
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// The calendar to use: its display name, its qualified name (e.g. "Calendar/Team" or
+    /// "alice@example.com:Calendar") or a unique prefix of either.
+    #[arg(long, global = true)]
+    pub calendar: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Interactively add events (the default).
+    Interactive,
+
+    /// Show the calendar in a full-screen day/week grid in which events can be added, edited and
+    /// deleted.
+    Tui,
+
+    /// Add a single event described by options.
+    Add(Box<EventSpec>),
+
+    /// Add the events from a TOML file of [[event]] tables whose keys correspond to the options of
+    /// `add` (e.g. date, start, end, title, free_busy, categories).
+    Import {
+        file: PathBuf,
+    },
+
+    /// List the events in a date range.
+    List {
+        /// The first date; today if missing.
+        #[arg(long)]
+        from: Option<String>,
+
+        /// The last date; the first date if missing.
+        #[arg(long)]
+        to: Option<String>,
+    },
+
+    /// Delete the events in a date range that match the given filters.
+    Delete {
+        #[command(flatten)]
+        filter: EventFilter,
+
+        /// Delete the events permanently instead of moving them to Deleted Items.
+        #[arg(long)]
+        hard: bool,
+
+        /// Do not ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Move the events in a date range that match the given filters by a fixed offset.
+    Shift {
+        #[command(flatten)]
+        filter: EventFilter,
+
+        /// The offset, e.g. "+1h", "-30m" or "+7d".
+        #[arg(long, allow_hyphen_values = true)]
+        by: String,
+
+        /// Shift whole recurring series instead of only the matching occurrences (which then
+        /// become exceptions).
+        #[arg(long)]
+        series: bool,
+
+        /// Do not ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Copy (or move) the events in a date range that match the given filters from the calendar
+    /// given by --calendar to another calendar.
+    Copy {
+        #[command(flatten)]
+        filter: EventFilter,
+
+        /// The calendar to copy the events to, given like --calendar.
+        #[arg(long)]
+        target: String,
+
+        /// Move the events instead of copying them.
+        #[arg(long = "move")]
+        move_events: bool,
+
+        /// Copy or move whole recurring series of which occurrences match; single occurrences
+        /// cannot be copied or moved.
+        #[arg(long)]
+        series: bool,
+
+        /// Changes the free/busy status of the copied or moved events, e.g. to "free" so that
+        /// events copied to a team calendar do not block anyone's time.
+        #[arg(long)]
+        free_busy: Option<String>,
+
+        /// Do not ask for confirmation.
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// List the available calendars.
+    Calendars,
+
+    /// Inspect or revert the items recorded in the journal.
+    #[command(subcommand)]
+    Journal(JournalCommand),
+}
+
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum JournalCommand {
+    /// List the runs recorded in the journal or, if a run is given, the items created by it.
+    List {
+        run_id: Option<String>,
+    },
+
+    /// List the items on the server created by this tool (optionally by a specific run), on any
+    /// machine.
+    Find {
+        run_id: Option<String>,
+    },
+
+    /// Delete all items created by a run.
+    Revert {
+        run_id: String,
+    },
+}
+
+
+/// Selects the events in a date range, optionally narrowed down by their subject and location.
+#[derive(Args, Clone, Debug)]
+pub struct EventFilter {
+    /// The first date.
+    #[arg(long, visible_alias = "date")]
+    pub from: String,
+
+    /// The last date; the first date if missing.
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// Only events whose subject is exactly this.
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Only events whose subject contains this text (ignoring case).
+    #[arg(long)]
+    pub subject_contains: Option<String>,
+
+    /// Only events whose location contains this text (ignoring case).
+    #[arg(long)]
+    pub location: Option<String>,
+}
+impl EventFilter {
+    /// Returns whether the event passes the subject and location filters.
+    pub fn matches(&self, event: &ExistingEvent) -> bool {
+        let contains_ignoring_case = |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+
+        if let Some(title) = &self.title {
+            if &event.title != title {
+                return false;
+            }
+        }
+        if let Some(subject_part) = &self.subject_contains {
+            if !contains_ignoring_case(&event.title, subject_part) {
+                return false;
+            }
+        }
+        if let Some(location_part) = &self.location {
+            match &event.location {
+                Some(location) if contains_ignoring_case(location, location_part) => {},
+                _ => return false,
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use msswap::ExchangeConfig;
+use serde::{Deserialize, Serialize};
+use toml::Table;
+
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Config {
+    #[serde(flatten)] pub exchange: ExchangeConfig,
+
+    /// The calendar to use if none is given on the command line: its display name, its qualified
+    /// name (e.g. "Calendar/Team" or "alice@example.com:Calendar") or a unique prefix of either.
+    #[serde(default)] pub default_calendar: Option<String>,
+
+    /// E-mail addresses of other mailboxes (e.g. colleagues or groups) whose shared calendars
+    /// should be offered too.
+    #[serde(default)] pub shared_mailboxes: Vec<String>,
+
+    /// Settings for specific calendars, keyed by their exact display name (not their qualified
+    /// name, nor a prefix of either).
+    #[serde(default)] pub calendars: BTreeMap<String, CalendarConfig>,
+
+    /// Event templates, keyed by their name.
+    #[serde(default)] pub templates: BTreeMap<String, EventTemplate>,
+
+    /// What to do when an edited event has been changed on the server since it was loaded.
+    #[serde(default)] pub conflict_handling: ConflictHandling,
+}
+impl Config {
+    pub fn calendar_config(&self, display_name: &str) -> Option<&CalendarConfig> {
+        self.calendars.get(display_name)
+    }
+
+    /// Returns the default reminder offset for events in the given calendar.
+    pub fn default_reminder_minutes(&self, display_name: &str) -> Option<u32> {
+        self.calendar_config(display_name)
+            .and_then(|cc| cc.reminder_minutes)
+    }
+}
+
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CalendarConfig {
+    /// How many minutes before the start of an event to remind of it. No reminder is set if
+    /// missing.
+    #[serde(default)] pub reminder_minutes: Option<u32>,
+}
+
+
+/// How to handle an event that has been changed by someone else while it was being edited.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictHandling {
+    /// Let Exchange merge the changes; fails if both changed the same property.
+    #[default] AutoResolve,
+
+    /// Reload the event and apply the changes again over the current version.
+    RefreshAndRetry,
+}
+
+
+/// A preset for events that are entered repeatedly.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EventTemplate {
+    pub title: String,
+    #[serde(default)] pub location: Option<String>,
+
+    /// The length of the event. The end time is asked for if missing.
+    #[serde(default)] pub duration_minutes: Option<u32>,
+
+    /// The free/busy status, e.g. "busy", "tentative" or "oof" (see the `!` statuses of one-line
+    /// events). Busy if missing.
+    #[serde(default)] pub free_busy_status: Option<String>,
+
+    /// How many minutes before the start of an event to remind of it. The calendar's default is
+    /// used if missing, unless `no_reminder` is set.
+    #[serde(default)] pub reminder_minutes: Option<u32>,
+    #[serde(default)] pub no_reminder: bool,
+
+    #[serde(default)] pub categories: Vec<String>,
+}
+
+
+/// Merges the values of `overlay` into `base`, recursing into tables present in both.
+fn merge_tables(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            },
+            (_, value) => {
+                base.insert(key, value);
+            },
+        }
+    }
+}
+
+/// Loads the configuration from the given file.
+///
+/// If a profile is given, the settings in the file's `[profiles.NAME]` table override the
+/// settings at the top level.
+pub(crate) fn load_config(path: &Path, profile: Option<&str>) -> Config {
+    let config_string = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e));
+    let mut config_table: Table = toml::from_str(&config_string)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {}", path.display(), e));
+
+    let profiles = match config_table.remove("profiles") {
+        None => Table::new(),
+        Some(toml::Value::Table(t)) => t,
+        Some(_) => panic!("failed to parse {}: profiles is not a table", path.display()),
+    };
+    if let Some(profile_name) = profile {
+        match profiles.get(profile_name) {
+            Some(toml::Value::Table(profile_table)) => merge_tables(&mut config_table, profile_table.clone()),
+            Some(_) => panic!("profile {:?} in {} is not a table", profile_name, path.display()),
+            None => panic!("no profile {:?} in {}", profile_name, path.display()),
+        }
+    }
+
+    config_table.try_into()
+        .unwrap_or_else(|e| panic!("failed to parse {}: {}", path.display(), e))
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::ItemId;
+
+
+/// The file (relative to the working directory, like config.toml) into which created items are
+/// recorded, one JSON object per line.
+pub(crate) const JOURNAL_PATH: &str = "journal.jsonl";
+
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct JournalEntry {
+    pub run_id: String,
+    pub item_id: String,
+    pub change_key: String,
+    pub calendar: String,
+    pub subject: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+impl JournalEntry {
+    pub fn to_item_id(&self) -> ItemId {
+        ItemId::new(
+            self.item_id.clone(),
+            self.change_key.clone(),
+        )
+    }
+}
+
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub calendar: String,
+    pub item_count: usize,
+}
+
+
+/// Generates an identifier for a new run, based on the current time and the process ID.
+pub(crate) fn new_run_id() -> String {
+    format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%SZ"), std::process::id())
+}
+
+pub(crate) fn append_entry<P: AsRef<Path>>(path: P, entry: &JournalEntry) {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .expect("failed to open journal for appending");
+    let mut line = serde_json::to_string(entry)
+        .expect("failed to serialize journal entry");
+    line.push('\n');
+    file.write_all(line.as_bytes())
+        .expect("failed to write journal entry");
+}
+
+pub(crate) fn read_entries<P: AsRef<Path>>(path: P) -> Vec<JournalEntry> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Vec::new(),
+        Err(e) => panic!("failed to open journal: {}", e),
+    };
+
+    let mut entries = Vec::new();
+    for (i, line_res) in BufReader::new(file).lines().enumerate() {
+        let line = line_res.expect("failed to read journal line");
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: JournalEntry = serde_json::from_str(&line)
+            .unwrap_or_else(|e| panic!("failed to parse journal line {}: {}", i + 1, e));
+        entries.push(entry);
+    }
+    entries
+}
+
+/// Rewrites the journal, dropping the entries for the given items.
+pub(crate) fn remove_entries<P: AsRef<Path>>(path: P, item_ids: &[ItemId]) {
+    let path = path.as_ref();
+    let remaining: Vec<JournalEntry> = read_entries(path)
+        .into_iter()
+        .filter(|e| !item_ids.iter().any(|ii| ii.id == e.item_id))
+        .collect();
+    write_entries(path, &remaining);
+}
+
+/// Rewrites the journal, pointing the entries for moved items to their new IDs and calendar.
+///
+/// The pairs consist of the old and the new item ID.
+pub(crate) fn replace_item_ids<P: AsRef<Path>>(path: P, moved_item_ids: &[(ItemId, ItemId)], calendar: &str) {
+    let path = path.as_ref();
+    let mut entries = read_entries(path);
+    for entry in &mut entries {
+        let Some((_, new_item_id)) = moved_item_ids.iter().find(|(old, _)| old.id == entry.item_id)
+            else { continue };
+        entry.item_id = new_item_id.id.clone();
+        entry.change_key = new_item_id.change_key.clone();
+        entry.calendar = calendar.to_owned();
+    }
+    write_entries(path, &entries);
+}
+
+fn write_entries(path: &Path, entries: &[JournalEntry]) {
+    let mut buf = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .expect("failed to serialize journal entry");
+        buf.push_str(&line);
+        buf.push('\n');
+    }
+    std::fs::write(path, buf)
+        .expect("failed to rewrite journal");
+}
+
+/// Summarizes the runs in the journal, in the order in which they first appear.
+pub(crate) fn summarize_runs(entries: &[JournalEntry]) -> Vec<RunSummary> {
+    let mut run_order = Vec::new();
+    let mut run_to_summary: BTreeMap<&str, RunSummary> = BTreeMap::new();
+    for entry in entries {
+        let summary = run_to_summary
+            .entry(&entry.run_id)
+            .or_insert_with(|| {
+                run_order.push(entry.run_id.as_str());
+                RunSummary {
+                    run_id: entry.run_id.clone(),
+                    calendar: entry.calendar.clone(),
+                    item_count: 0,
+                }
+            });
+        summary.item_count += 1;
+    }
+
+    run_order.into_iter()
+        .map(|run_id| run_to_summary.remove(run_id).unwrap())
+        .collect()
+}
@@ -1,32 +1,222 @@
+mod agenda;
+mod cli;
+mod config;
+mod journal;
 mod model;
+mod parse;
+mod readline;
+mod spec;
+mod state;
+mod timezones;
+mod tui;
 mod xml;
 
 
-use std::io::stdin;
-
-use chrono::{DateTime, Days, Local, LocalResult, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono::{DateTime, Days, Local, LocalResult, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
 use chrono_tz::Tz;
-use msswap::ExchangeConfig;
+use clap::Parser;
 use reqwest::Client;
 
-use crate::model::{FolderId, FreeBusyStatus, NewEvent};
-use crate::xml::{create_event, extract_found_calendars, extract_success, search_for_calendars};
+use crate::cli::{Cli, Command, EventFilter, JournalCommand};
+use crate::config::{Config, ConflictHandling, EventTemplate, load_config};
+use crate::journal::{JOURNAL_PATH, JournalEntry};
+use crate::model::{
+    assign_calendar_paths, Attendee, AttendeeKind, BodyType, Calendar, CalendarItemType, ConflictResolution, CreatedItem,
+    DeleteType, EventBody, EventChanges, EventTime, ExistingEvent, FreeBusyStatus, Importance, ItemId, Mailbox, NewEvent,
+    Sensitivity, sort_calendars, TransferMode,
+};
+use crate::parse::{EventLine, EventLineTime, TimeSpec};
+use crate::spec::{EventSpec, ImportFile};
+use crate::state::STATE_PATH;
+use crate::xml::{
+    create_event, delete_items, extract_delete_success, extract_found_calendars, extract_found_items,
+    extract_got_event, extract_resolved_names, extract_success, extract_update_success, find_tagged_items,
+    extract_transfer_success, get_calendar_folder, get_event, get_recurring_master, obtain_calendar_view, resolve_names,
+    search_for_calendars, transfer_items, update_event,
+};
+
+
+/// The maximum number of matching time zones to offer to pick from.
+const MAX_TIME_ZONE_CANDIDATES: usize = 20;
+
+/// The maximum number of items to delete with a single DeleteItem request.
+const DELETE_BATCH_SIZE: usize = 100;
+
+/// The maximum number of items to copy or move with a single CopyItem or MoveItem request.
+const TRANSFER_BATCH_SIZE: usize = 100;
 
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 enum TimeResult {
-    Time(DateTime<Utc>),
+    Time(DateTime<Tz>),
     InputAgain,
     GiveUp,
 }
 
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Session {
+    /// Identifies this session's items in the journal.
+    pub run_id: String,
+
+    /// Items created during this session, oldest first.
+    pub created_items: Vec<CreatedItem>,
+
+    /// The time zone in which times are entered, unless overridden for a specific event.
+    pub time_zone: Tz,
+}
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            run_id: journal::new_run_id(),
+            created_items: Vec::new(),
+            time_zone: timezones::local_time_zone(),
+        }
+    }
+}
+
+
+async fn post_ews(client: &Client, config: &Config, body: Vec<u8>, purpose: &str) -> Vec<u8> {
+    let response = client.post(&config.exchange.ews_url)
+        .header("Content-Type", "text/xml")
+        .body(body)
+        .send().await
+        .unwrap_or_else(|e| panic!("failed to request {}: {}", purpose, e));
+    response
+        .bytes().await
+        .unwrap_or_else(|e| panic!("failed to obtain {} response bytes: {}", purpose, e))
+        .to_vec()
+}
+
+/// Deletes the given items in batches, returning the outcome for each item in order.
+async fn delete_items_batched(client: &Client, config: &Config, item_ids: &[ItemId], delete_type: DeleteType) -> Vec<Result<(), String>> {
+    let mut outcomes = Vec::with_capacity(item_ids.len());
+    for batch in item_ids.chunks(DELETE_BATCH_SIZE) {
+        let delete_body = delete_items(batch, delete_type);
+        let delete_bytes = post_ews(client, config, delete_body, "deletion").await;
+        outcomes.extend(extract_delete_success(delete_bytes));
+    }
+    outcomes
+}
+
+/// Deletes the given items and drops them from the journal.
+///
+/// Items that no longer exist on the server are also dropped from the journal and count as
+/// deleted. Returns the outcome for each item in order.
+async fn delete_journaled_items(client: &Client, config: &Config, item_ids: &[ItemId], delete_type: DeleteType) -> Vec<Result<(), String>> {
+    let mut outcomes = delete_items_batched(client, config, item_ids, delete_type).await;
+
+    let mut gone_item_ids = Vec::new();
+    for (item_id, outcome) in item_ids.iter().zip(outcomes.iter_mut()) {
+        match outcome {
+            Ok(()) => gone_item_ids.push(item_id.clone()),
+            Err(code) if code == "ErrorItemNotFound" => {
+                gone_item_ids.push(item_id.clone());
+                *outcome = Ok(());
+            },
+            Err(code) => println!("> failed to delete item {}: {}", item_id.id, code),
+        }
+    }
+    journal::remove_entries(JOURNAL_PATH, &gone_item_ids);
+    outcomes
+}
+
+/// Applies the changes to an existing event, returning the updated item ID or the response code.
+///
+/// If the event has been changed on the server since it was loaded, the conflict is handled as
+/// configured.
+async fn apply_event_changes(client: &Client, config: &Config, item_id: &ItemId, changes: &EventChanges) -> Result<ItemId, String> {
+    let conflict_resolution = match config.conflict_handling {
+        ConflictHandling::AutoResolve => ConflictResolution::AutoResolve,
+        ConflictHandling::RefreshAndRetry => ConflictResolution::NeverOverwrite,
+    };
+    let update_body = update_event(item_id, changes, conflict_resolution);
+    let update_bytes = post_ews(client, config, update_body, "update").await;
+    let outcome = extract_update_success(update_bytes).pop()
+        .unwrap_or_else(|| Err("no response".to_owned()));
+    match outcome {
+        Err(code) if code == "ErrorIrresolvableConflict" && config.conflict_handling == ConflictHandling::RefreshAndRetry => {
+            // obtain the current change key and try once more
+            let get_body = get_event(item_id);
+            let get_bytes = post_ews(client, config, get_body, "event").await;
+            let current_event = extract_got_event(get_bytes)?;
+
+            let retry_body = update_event(&current_event.item_id, changes, conflict_resolution);
+            let retry_bytes = post_ews(client, config, retry_body, "update").await;
+            extract_update_success(retry_bytes).pop()
+                .unwrap_or_else(|| Err("no response".to_owned()))
+        },
+        other => other,
+    }
+}
+
+/// Finds the calendars in the user's mailbox and the shared mailboxes from the configuration,
+/// sorted by owner and path.
+async fn get_calendars(client: &Client, config: &Config) -> Vec<Calendar> {
+    let sfc_body = search_for_calendars("msgfolderroot", None);
+    let sfc_bytes = post_ews(client, config, sfc_body, "calendar folders").await;
+    let mut calendars = extract_found_calendars(sfc_bytes, None)
+        .unwrap_or_else(|code| panic!("failed to find calendar folders: {}", code));
+
+    for mailbox in &config.shared_mailboxes {
+        // other mailboxes usually share only the calendar, so start from there
+        let gcf_body = get_calendar_folder(mailbox);
+        let gcf_bytes = post_ews(client, config, gcf_body, "shared calendar folder").await;
+        match extract_found_calendars(gcf_bytes, Some(mailbox)) {
+            Ok(main_calendars) => calendars.extend(main_calendars),
+            Err(code) => {
+                eprintln!("warning: cannot access the calendar of {}: {}", mailbox, code);
+                continue;
+            },
+        }
+
+        let sfc_body = search_for_calendars("calendar", Some(mailbox));
+        let sfc_bytes = post_ews(client, config, sfc_body, "shared calendar folders").await;
+        match extract_found_calendars(sfc_bytes, Some(mailbox)) {
+            Ok(sub_calendars) => calendars.extend(sub_calendars),
+            Err(code) => eprintln!("warning: cannot search the calendars below that of {}: {}", mailbox, code),
+        }
+    }
+
+    assign_calendar_paths(&mut calendars);
+    sort_calendars(&mut calendars);
+    calendars
+}
+
+/// Finds the items in the given calendar that were created by this tool, optionally restricted
+/// to those created in a specific batch (run).
+async fn get_tagged_items(client: &Client, config: &Config, calendar: &Calendar, batch_id: Option<&str>) -> Vec<ExistingEvent> {
+    let mut events = Vec::new();
+    loop {
+        let find_body = find_tagged_items(&calendar.folder_id, batch_id, events.len());
+        let find_bytes = post_ews(client, config, find_body, "tagged items").await;
+        let (is_last, page_events) = extract_found_items(find_bytes);
+        let page_was_empty = page_events.is_empty();
+        events.extend(page_events);
+        if is_last || page_was_empty {
+            break;
+        }
+    }
+    events
+}
+
+/// Obtains the events (including occurrences of recurring events) overlapping the given time
+/// range.
+async fn get_events(client: &Client, config: &Config, calendar: &Calendar, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<ExistingEvent> {
+    let view_body = obtain_calendar_view(&calendar.folder_id, start, end);
+    let view_bytes = post_ews(client, config, view_body, "calendar view").await;
+    let (_is_last, events) = extract_found_items(view_bytes);
+    events
+}
+
+/// Obtains the events (including occurrences of recurring events) on the given date.
+async fn get_agenda(client: &Client, config: &Config, calendar: &Calendar, date: &NaiveDate, tz: &Tz) -> Vec<ExistingEvent> {
+    let (day_start, day_end) = agenda::day_bounds(date, tz);
+    get_events(client, config, calendar, day_start, day_end).await
+}
+
 fn read_stdin_line() -> String {
-    let stdin = stdin();
-    let mut buf = String::new();
-    stdin.read_line(&mut buf)
-        .expect("failed to read line");
-    buf
+    readline::read_line(true)
 }
 
 fn read_stdin_line_trimmed() -> String {
@@ -34,7 +224,7 @@ fn read_stdin_line_trimmed() -> String {
     line.trim().to_owned()
 }
 
-fn get_time_assuming_timezone<T: TimeZone>(date: &NaiveDate, time: &NaiveTime, tz: &T) -> TimeResult {
+fn get_time_assuming_timezone(date: &NaiveDate, time: &NaiveTime, tz: &Tz) -> TimeResult {
     let local_time = match tz.from_local_datetime(&date.and_time(*time)) {
         LocalResult::None => {
             println!("> no such time; try again");
@@ -56,224 +246,1699 @@ fn get_time_assuming_timezone<T: TimeZone>(date: &NaiveDate, time: &NaiveTime, t
         },
         LocalResult::Single(dt) => dt,
     };
-    TimeResult::Time(local_time.with_timezone(&Utc))
+    TimeResult::Time(local_time)
+}
+
+/// Asks the user to pick one of multiple matching time zones.
+fn pick_time_zone(candidates: &[Tz]) -> Option<Tz> {
+    loop {
+        println!("> multiple time zones match; pick one (or empty to cancel):");
+        for (i, tz) in candidates.iter().enumerate() {
+            println!("> {}. {}", i, tz.name());
+        }
+        let pick_line = read_stdin_line_trimmed();
+        if pick_line.is_empty() {
+            return None;
+        }
+        match pick_line.parse::<usize>() {
+            Ok(i) if i < candidates.len() => return Some(candidates[i]),
+            Ok(i) => println!("> index {} is out of bounds", i),
+            Err(e) => println!("> failed to parse {:?}: {}", pick_line, e),
+        }
+    }
+}
+
+/// Resolves a time zone query (see [`timezones::find_time_zones`]), asking the user to pick if
+/// it is ambiguous.
+fn resolve_time_zone(query: &str) -> Option<Tz> {
+    let candidates = timezones::find_time_zones(query);
+    if candidates.is_empty() {
+        println!("> unknown time zone {:?}", query);
+        None
+    } else if candidates.len() == 1 {
+        Some(candidates[0])
+    } else if candidates.len() > MAX_TIME_ZONE_CANDIDATES {
+        println!("> {} time zones match {:?}; please be more specific", candidates.len(), query);
+        None
+    } else {
+        pick_time_zone(&candidates)
+    }
+}
+
+/// Asks for a new session time zone. Returns `None` if the user keeps the current one.
+fn ask_time_zone() -> Option<Tz> {
+    loop {
+        println!("> Time zone? [IANA name, city or abbreviation; \"local\" for local time; empty to keep the current one]");
+        let timezone_line = read_stdin_line_trimmed();
+        if timezone_line.is_empty() {
+            return None;
+        } else if timezone_line == "local" {
+            return Some(timezones::local_time_zone());
+        }
+        if let Some(tz) = resolve_time_zone(&timezone_line) {
+            return Some(tz);
+        }
+    }
 }
 
-fn get_time(date: &NaiveDate, time_kind: &str) -> Option<DateTime<Utc>> {
-    let mut timezone: Option<Tz> = None;
+/// Asks for a time on the given date.
+///
+/// The time is interpreted in the event's time zone, which starts out as the session's time zone.
+/// A time zone given after the time (e.g. `0900 PST`) changes the event's time zone; the `tz`
+/// command changes the session's time zone, which is kept for all following events.
+///
+/// If the start of the event is given, the time is its end: a duration (e.g. `+45m`) is then
+/// accepted as well as a day offset (e.g. `0200 +1d`) for events ending on a later day, and times
+/// before the start are rejected.
+fn get_time(date: &NaiveDate, time_kind: &str, start: Option<&DateTime<Tz>>, session: &mut Session, event_tz: &mut Tz) -> Option<DateTime<Tz>> {
+    let syntax_hint = if start.is_some() {
+        "e.g. 0930, 9:30, 2pm, 0200 +1d or +1h30; optionally followed by a time zone for this event"
+    } else {
+        "e.g. 0930, 9:30, 9.30 or 2pm; optionally followed by a time zone for this event"
+    };
     loop {
-        let timezone_name = timezone.map(|tz| tz.name()).unwrap_or("local time");
-        println!("> {} in {}? [hhmm] (or \"tz\" to set timezone or \"nvm\" to give up)", time_kind, timezone_name);
+        println!("> {} in {}? [{}] (or \"tz\" to set the session time zone or \"nvm\" to give up)", time_kind, event_tz.name(), syntax_hint);
         let time_line = read_stdin_line_trimmed();
         if time_line == "nvm" {
             return None;
         } else if time_line == "tz" {
-            loop {
-                println!("> IANA timezone (or empty for local time)?");
-                let timezone_line = read_stdin_line_trimmed();
-                if timezone_line.len() == 0 {
-                    timezone = None;
-                    break;
-                }
-                let mut tz_found = false;
-                for tz in chrono_tz::TZ_VARIANTS {
-                    if tz.name() == &timezone_line {
-                        timezone = Some(tz);
-                        tz_found = true;
-                        break;
-                    }
-                }
-                if tz_found {
-                    break;
-                } else {
-                    println!("unknown timezone");
-                }
+            if let Some(tz) = ask_time_zone() {
+                session.time_zone = tz;
+                *event_tz = tz;
+                println!("> session time zone is now {}", tz.name());
             }
             continue;
         }
-        let t = match NaiveTime::parse_from_str(&time_line, "%H%M") {
-            Ok(v) => v,
+
+        let time_input = match parse::parse_time_input(&time_line) {
+            Ok(ti) => ti,
             Err(e) => {
-                println!("> failed to parse: {}", e);
+                println!("> {}", e);
                 continue;
             },
         };
-        let dt_result = if let Some(tz) = timezone {
-            get_time_assuming_timezone(date, &t, &tz)
-        } else {
-            get_time_assuming_timezone(date, &t, &Local)
+        if let Some(zone_query) = &time_input.time_zone_query {
+            match resolve_time_zone(zone_query) {
+                Some(tz) => *event_tz = tz,
+                None => continue,
+            }
+        }
+
+        let dt = match (time_input.spec, start) {
+            (TimeSpec::Duration(_), None) => {
+                println!("> a duration can only be given for the end time");
+                continue;
+            },
+            (TimeSpec::Duration(duration), Some(start_time)) => {
+                match start_time.checked_add_signed(duration) {
+                    Some(end_time) => end_time.with_timezone(event_tz),
+                    None => {
+                        println!("> the duration is too long");
+                        continue;
+                    },
+                }
+            },
+            (TimeSpec::TimeOfDay { days_later, .. }, None) if days_later > 0 => {
+                println!("> a day offset can only be given for the end time");
+                continue;
+            },
+            (TimeSpec::TimeOfDay { time, days_later }, _) => {
+                let Some(actual_date) = date.checked_add_days(Days::new(days_later.into())) else {
+                    println!("> the day offset is too large");
+                    continue;
+                };
+                match get_time_assuming_timezone(&actual_date, &time, event_tz) {
+                    TimeResult::Time(dt) => dt,
+                    TimeResult::GiveUp => return None,
+                    TimeResult::InputAgain => continue,
+                }
+            },
         };
-        match dt_result {
-            TimeResult::Time(dt) => return Some(dt),
-            TimeResult::GiveUp => return None,
-            TimeResult::InputAgain => {}, // loop again
+
+        if let Some(start_time) = start {
+            if dt < *start_time {
+                println!("> the end time is before the start time; append \"+1d\" if the event ends on the next day");
+                continue;
+            }
         }
+        return Some(dt);
     }
 }
 
-async fn add_event_loop(client: &mut Client, config: &ExchangeConfig, calendar_folder: &FolderId, date: &NaiveDate) -> bool {
-    let mut ask_free_busy_state = false;
+/// Parses `undo` or `undo N`, returning the number of items to undo.
+///
+/// Returns `None` if the line is not an undo command and `Some(Err(...))` if it is one but the
+/// count cannot be parsed.
+fn parse_undo_command(line: &str) -> Option<Result<usize, String>> {
+    let rest = line.strip_prefix("undo")?;
+    if rest.is_empty() {
+        return Some(Ok(1));
+    }
+    if !rest.starts_with(' ') {
+        return None;
+    }
+    match rest.trim().parse() {
+        Ok(0) => Some(Err("nothing to undo with a count of 0".to_owned())),
+        Ok(n) => Some(Ok(n)),
+        Err(e) => Some(Err(format!("failed to parse undo count {:?}: {}", rest.trim(), e))),
+    }
+}
+
+async fn undo_created_items(client: &Client, config: &Config, session: &mut Session, count: usize) {
+    if session.created_items.is_empty() {
+        println!("> nothing to undo");
+        return;
+    }
+    let actual_count = count.min(session.created_items.len());
+    if actual_count < count {
+        println!("> only {} item(s) created in this session; undoing those", actual_count);
+    }
+
+    let undo_start = session.created_items.len() - actual_count;
+    let undo_items: Vec<CreatedItem> = session.created_items.drain(undo_start..).collect();
+    let item_ids: Vec<ItemId> = undo_items.iter()
+        .map(|ci| ci.item_id.clone())
+        .collect();
+
+    let outcomes = delete_journaled_items(client, config, &item_ids, DeleteType::MoveToDeletedItems).await;
+
+    // keep the items that could not be deleted (or for which the server reported nothing) so that
+    // undoing them can be retried
+    let mut failed_items = Vec::new();
+    for (i, item) in undo_items.into_iter().enumerate().rev() {
+        match outcomes.get(i) {
+            Some(Ok(())) => println!("> undone: {}", item.title),
+            Some(Err(_)) => failed_items.push(item),
+            None => {
+                println!("> no response for the deletion of {}", item.title);
+                failed_items.push(item);
+            },
+        }
+    }
+    if !failed_items.is_empty() {
+        println!("> warning: {} of {} deletions failed; they can be undone again", failed_items.len(), actual_count);
+        failed_items.reverse();
+        session.created_items.extend(failed_items);
+    }
+}
+
+/// Resolves a (partial) name or e-mail address to a mailbox, asking the user to choose if there are
+/// multiple candidates.
+///
+/// Returns `None` if the name cannot be resolved or the user does not choose a candidate.
+async fn resolve_mailbox(client: &Client, config: &Config, unresolved: &str) -> Option<Mailbox> {
+    let resolve_body = resolve_names(unresolved);
+    let resolve_bytes = post_ews(client, config, resolve_body, "name resolution").await;
+    let mut candidates = extract_resolved_names(resolve_bytes);
+
+    if candidates.is_empty() {
+        if unresolved.contains('@') {
+            // perhaps an external address
+            return Some(Mailbox::new(None, unresolved.to_owned()));
+        }
+        println!("> no one found for {:?}", unresolved);
+        return None;
+    } else if candidates.len() == 1 {
+        return candidates.pop();
+    }
+
     loop {
-        println!("> Add an event on {}? [ynf]", date.format("%Y-%m-%d"));
-        let add_line = read_stdin_line_trimmed();
+        println!("> {:?} is ambiguous; pick one (or empty to skip):", unresolved);
+        for (i, candidate) in candidates.iter().enumerate() {
+            println!("> {}. {} <{}>", i, candidate.name.as_deref().unwrap_or(""), candidate.email_address);
+        }
+        let pick_line = read_stdin_line_trimmed();
+        if pick_line.is_empty() {
+            return None;
+        }
+        match pick_line.parse::<usize>() {
+            Ok(i) if i < candidates.len() => return Some(candidates.swap_remove(i)),
+            Ok(i) => println!("> index {} is out of bounds", i),
+            Err(e) => println!("> failed to parse {:?}: {}", pick_line, e),
+        }
+    }
+}
 
-        if add_line == "y" {
-            // break out of this obstinate loop
+/// Asks for a comma-separated list of attendees of the given kind and resolves them.
+///
+/// Returns `None` if the user wants to give up on the event.
+async fn ask_attendee_list(client: &Client, config: &Config, kind: AttendeeKind, description: &str) -> Option<Vec<Attendee>> {
+    'asking: loop {
+        println!("> {}? [comma-separated names or addresses, empty for none, q=quit]", description);
+        let attendees_line = read_stdin_line_trimmed();
+        if attendees_line == "q" {
+            return None;
+        }
+
+        let mut attendees = Vec::new();
+        for unresolved in attendees_line.split(',').map(|a| a.trim()).filter(|a| !a.is_empty()) {
+            match resolve_mailbox(client, config, unresolved).await {
+                Some(mailbox) => {
+                    println!("> {} -> {}", unresolved, mailbox.email_address);
+                    attendees.push(Attendee::new(mailbox, kind));
+                },
+                None => {
+                    println!("> please enter the list again");
+                    continue 'asking;
+                },
+            }
+        }
+        return Some(attendees);
+    }
+}
+
+/// Reads lines until one consisting only of a period.
+fn read_stdin_block() -> String {
+    let mut block = String::new();
+    loop {
+        // pasted lines would only clutter the history
+        let line = readline::read_line(false);
+        if line.is_empty() {
+            // end of input
             break;
-        } else if add_line == "n" {
-            // exit the app
-            return false;
-        } else if add_line == "f" {
-            // user wants to add a special free-busy state
-            ask_free_busy_state = true;
+        }
+        let trimmed_end = line.trim_end_matches(['\r', '\n']);
+        if trimmed_end.trim() == "." {
             break;
         }
-
-        // otherwise, ask again
+        block.push_str(trimmed_end);
+        block.push('\n');
     }
+    block
+}
 
-    let start = match get_time(&date, "Start time") {
-        None => return true,
-        Some(dt) => dt,
-    };
-    let mut end = match get_time(&date, "End time") {
-        None => return true,
-        Some(dt) => dt,
+/// Asks for the body, categories, sensitivity and importance of the event.
+///
+/// Returns `false` if the user wants to give up on the event.
+fn ask_event_details(new_event: &mut NewEvent) -> bool {
+    let body_type = loop {
+        println!("> Body format? [t=text, h=HTML, empty for no body, q=quit]");
+        let format_line = read_stdin_line_trimmed();
+        match format_line.as_str() {
+            "" => break None,
+            "t" => break Some(BodyType::Text),
+            "h" => break Some(BodyType::Html),
+            "q" => return false,
+            _ => {},
+        }
     };
-    if end < start {
-        println!("Warning: end time before start time; increasing end date");
-        end = end.checked_add_days(Days::new(1)).unwrap();
+    if let Some(body_type) = body_type {
+        println!("> Body? (end with a line containing only \".\")");
+        let content = read_stdin_block();
+        new_event.body = Some(EventBody::new(body_type, content));
     }
 
-    println!("> Event name?");
-    let name = read_stdin_line_trimmed();
+    println!("> Categories? [comma-separated, empty for none]");
+    let categories_line = read_stdin_line_trimmed();
+    new_event.categories = parse::parse_categories(&categories_line);
 
-    println!("> Location?");
-    let location_line = read_stdin_line_trimmed();
-    let location = if location_line.len() > 0 {
-        Some(location_line)
-    } else {
-        None
+    new_event.sensitivity = loop {
+        println!("> Sensitivity? [n=normal, p=personal, v=private, c=confidential, empty for normal, q=quit]");
+        let sensitivity_line = read_stdin_line_trimmed();
+        if sensitivity_line == "q" {
+            return false;
+        } else if sensitivity_line.is_empty() {
+            break Sensitivity::Normal;
+        } else if let Some(sensitivity) = parse::parse_sensitivity(&sensitivity_line) {
+            break sensitivity;
+        }
     };
 
-    let free_busy_state = if ask_free_busy_state {
-        loop {
-            println!("> Free/busy state? [f=free, b=busy, t=tentative, o=out-of-office, e=elsewhere, n=no-data, q=quit]");
-            let fbs_line = read_stdin_line_trimmed();
-            match fbs_line.as_str() {
-                "f" => break FreeBusyStatus::Free,
-                "b" => break FreeBusyStatus::Busy,
-                "t" => break FreeBusyStatus::Tentative,
-                "o" => break FreeBusyStatus::OutOfOffice,
-                "e" => break FreeBusyStatus::WorkingElsewhere,
-                "n" => break FreeBusyStatus::NoData,
-                "q" => return true,
-                _ => {},
-            }
+    new_event.importance = loop {
+        println!("> Importance? [l=low, n=normal, h=high, empty for normal, q=quit]");
+        let importance_line = read_stdin_line_trimmed();
+        if importance_line == "q" {
+            return false;
+        } else if importance_line.is_empty() {
+            break Importance::Normal;
+        } else if let Some(importance) = parse::parse_importance(&importance_line) {
+            break importance;
         }
-    } else {
-        FreeBusyStatus::Busy
     };
 
-    let new_event = NewEvent::new(
-        start,
-        end,
-        name,
-        location,
-        Some(free_busy_state),
-    );
+    true
+}
 
-    // add this event
-    let add_body = create_event(&new_event, &calendar_folder);
-    let add_response = client.post(&config.ews_url)
-        .header("Content-Type", "text/xml")
-        .body(add_body)
-        .send().await.expect("failed to request addition");
-    let add_bytes = add_response
-        .bytes().await.expect("failed to obtain addition response bytes")
-        .to_vec();
-    extract_success(add_bytes);
+/// Warns if Exchange does not know the time zones of the event or if it overlaps existing events
+/// on the given date.
+fn warn_about_event_time(date: &NaiveDate, session: &Session, day_events: &[ExistingEvent], event_time: &EventTime) {
+    for tz in [event_time.start_time_zone(), event_time.end_time_zone()] {
+        if timezones::windows_zone_id(tz).is_none() {
+            println!("> warning: Exchange does not know the time zone {}; the event will be stored without it", tz.name());
+        }
+    }
 
-    true
+    let (start, end) = event_time.utc_bounds();
+    let overlap_count = day_events.iter()
+        .filter(|e| agenda::overlaps(e, start, end))
+        .count();
+    if overlap_count > 0 {
+        println!("> warning: this event overlaps {} existing event(s) (marked with !!)", overlap_count);
+        agenda::print_agenda(date, &session.time_zone, day_events, Some((start, end)));
+    }
 }
 
-async fn interaction_loop(mut client: Client, config: &ExchangeConfig, calendar_folder: &FolderId) {
-    loop {
-        let date = loop {
-            println!("> Date? [yyyymmdd] (or \"nvm\" to end)");
-            let date_line = read_stdin_line_trimmed();
-            if date_line == "nvm" {
-                return;
-            }
-            let d = match NaiveDate::parse_from_str(&date_line, "%Y%m%d") {
-                Ok(v) => v,
-                Err(e) => {
-                    println!("> failed to parse: {}", e);
-                    continue;
-                }
-            };
-            break d;
-        };
+/// Creates the event in the calendar and records it in the journal and the session.
+async fn create_and_record_event(client: &Client, config: &Config, calendar: &Calendar, session: &mut Session, new_event: &NewEvent) {
+    let (start, end) = new_event.time.utc_bounds();
+    let add_body = create_event(new_event, &calendar.folder_id, &session.run_id);
+    let add_bytes = post_ews(client, config, add_body, "addition").await;
+    for item_id in extract_success(add_bytes) {
+        journal::append_entry(JOURNAL_PATH, &JournalEntry {
+            run_id: session.run_id.clone(),
+            item_id: item_id.id.clone(),
+            change_key: item_id.change_key.clone(),
+            calendar: calendar.display_name.clone(),
+            subject: new_event.title.clone(),
+            start_time: start,
+            end_time: end,
+        });
+        session.created_items.push(CreatedItem::new(
+            item_id,
+            new_event.title.clone(),
+        ));
+    }
 
-        loop {
-            let keep_looping = add_event_loop(&mut client, config, calendar_folder, &date).await;
-            if !keep_looping {
-                break;
+    if let Some(location) = &new_event.location {
+        state::remember_location(STATE_PATH, location);
+        readline::add_completion_candidates([location.clone()]);
+    }
+}
+
+/// Converts the times of a one-line event on the given date into event times in the given time
+/// zone.
+///
+/// Returns `None` if the times are invalid (which is reported) or the user gives up.
+fn event_time_from_line(date: &NaiveDate, line_time: EventLineTime, tz: &Tz) -> Option<EventTime> {
+    let (start_time, end_spec) = match line_time {
+        EventLineTime::AllDay => {
+            return Some(EventTime::AllDay { first_date: *date, last_date: *date, time_zone: *tz });
+        },
+        EventLineTime::Range { start, end } => (start, end),
+    };
+
+    let start = match get_time_assuming_timezone(date, &start_time, tz) {
+        TimeResult::Time(dt) => dt,
+        TimeResult::InputAgain | TimeResult::GiveUp => return None,
+    };
+    let end = match end_spec {
+        TimeSpec::Duration(duration) => {
+            let Some(end) = start.checked_add_signed(duration) else {
+                println!("> the duration is too long");
+                return None;
+            };
+            end
+        },
+        TimeSpec::TimeOfDay { time, days_later } => {
+            let Some(end_date) = date.checked_add_days(Days::new(days_later.into())) else {
+                println!("> the day offset is too large");
+                return None;
+            };
+            match get_time_assuming_timezone(&end_date, &time, tz) {
+                TimeResult::Time(dt) => dt,
+                TimeResult::InputAgain | TimeResult::GiveUp => return None,
             }
+        },
+    };
+    if end < start {
+        println!("> the end time is before the start time; append \"+1d\" if the event ends on the next day");
+        return None;
+    }
+    Some(EventTime::Instants { start, end })
+}
+
+/// Shows the event and asks whether to create it.
+fn confirm_new_event(new_event: &NewEvent, tz: &Tz) -> bool {
+    loop {
+        println!("> {}", agenda::describe_new_event(new_event, tz));
+        println!("> Create this event? [y/n]");
+        let confirm_line = read_stdin_line_trimmed();
+        if confirm_line == "y" {
+            return true;
+        } else if confirm_line == "n" {
+            return false;
         }
     }
 }
 
+/// Adds an event entered on a single line after a single confirmation.
+async fn add_event_from_line(client: &Client, config: &Config, calendar: &Calendar, session: &mut Session, date: &NaiveDate, day_events: &[ExistingEvent], event_line: EventLine) {
+    let Some(event_time) = event_time_from_line(date, event_line.time, &session.time_zone)
+        else { return };
+    let free_busy_status = event_line.free_busy_status.unwrap_or(if event_time.is_all_day() {
+        FreeBusyStatus::Free
+    } else {
+        FreeBusyStatus::Busy
+    });
 
-#[tokio::main]
-async fn main() {
-    env_logger::init();
+    warn_about_event_time(date, session, day_events, &event_time);
 
-    // load config
-    let config: ExchangeConfig = {
-        let config_string = std::fs::read_to_string("config.toml")
-            .expect("failed to read config.toml");
-        toml::from_str(&config_string)
-            .expect("failed to parse config.toml")
-    };
+    let mut new_event = NewEvent::new(
+        event_time,
+        event_line.title,
+        event_line.location,
+        Some(free_busy_status),
+    );
+    new_event.reminder_minutes = config.default_reminder_minutes(&calendar.display_name);
 
-    let client = msswap::initial_auth(&config).await;
+    if !confirm_new_event(&new_event, &session.time_zone) {
+        return;
+    }
 
-    let sfc_body = search_for_calendars();
-    let sfc_response = client.post(&config.ews_url)
-        .header("Content-Type", "text/xml")
-        .body(sfc_body)
-        .send().await.expect("failed to request calendar folders");
-    let sfc_bytes = sfc_response
-        .bytes().await.expect("failed to obtain calendar folders response bytes")
-        .to_vec();
-    let calendars = extract_found_calendars(sfc_bytes);
-
-    let calendar_index = {
-        loop {
-            println!("> Pick a calendar:");
-            for (i, calendar) in calendars.iter().enumerate() {
-                println!("> {}. {}", i, calendar.display_name);
-            }
+    create_and_record_event(client, config, calendar, session, &new_event).await;
+}
 
-            let buf = read_stdin_line_trimmed();
+/// Reads a pasted schedule, shows the events parsed from it for editing and creates them.
+async fn paste_schedule(client: &Client, config: &Config, calendar: &Calendar, session: &mut Session, date: &NaiveDate, day_events: &[ExistingEvent]) {
+    println!("> Paste the schedule for {}, then enter a line consisting only of \".\":", date.format("%Y-%m-%d"));
+    let block = read_stdin_block();
 
-            // try to parse
-            let calendar_index: usize = match buf.parse() {
-                Ok(u) => u,
-                Err(e) => {
-                    println!("failed to parse {:?}: {}", buf, e);
-                    continue;
-                },
-            };
-            if calendar_index >= calendars.len() {
-                println!("index {} is out of bounds, try again", calendar_index);
-                continue;
-            }
+    let default_reminder_minutes = config.default_reminder_minutes(&calendar.display_name);
+    let to_new_event = |event_line: EventLine, tz: &Tz| -> Option<NewEvent> {
+        let event_time = event_time_from_line(date, event_line.time, tz)?;
+        let free_busy_status = event_line.free_busy_status.unwrap_or(if event_time.is_all_day() {
+            FreeBusyStatus::Free
+        } else {
+            FreeBusyStatus::Busy
+        });
+        let mut new_event = NewEvent::new(
+            event_time,
+            event_line.title,
+            event_line.location,
+            Some(free_busy_status),
+        );
+        new_event.reminder_minutes = default_reminder_minutes;
+        Some(new_event)
+    };
 
-            break calendar_index;
+    let mut new_events = Vec::new();
+    for line in block.lines().filter(|l| !l.trim().is_empty()) {
+        match parse::parse_schedule_line(line) {
+            Ok(event_line) => {
+                if let Some(new_event) = to_new_event(event_line, &session.time_zone) {
+                    new_events.push(new_event);
+                }
+            },
+            Err(e) => println!("> skipping line: {}", e),
+        }
+    }
+    for new_event in &new_events {
+        warn_about_event_time(date, session, day_events, &new_event.time);
+    }
+
+    loop {
+        if new_events.is_empty() {
+            println!("> no events to create");
+            return;
+        }
+
+        println!("> parsed events:");
+        for (i, new_event) in new_events.iter().enumerate() {
+            println!("> {:>3}. {}", i, agenda::describe_new_event(new_event, &session.time_zone));
+        }
+        println!("> Create these events? [y=yes, q=quit, \"d N\" to drop event N, \"e N EVENT\" to replace event N with a one-line event like \"0900-1030 Keynote @Hall A\"]");
+        let command_line = read_stdin_line_trimmed();
+        if command_line == "y" {
+            break;
+        } else if command_line == "q" {
+            return;
         }
-    };
 
-    interaction_loop(client, &config, &calendars[calendar_index].folder_id).await;
+        let mut pieces = command_line.splitn(3, ' ');
+        let command = pieces.next().unwrap_or("");
+        let index = match pieces.next().map(|i| i.parse::<usize>()) {
+            Some(Ok(i)) if i < new_events.len() => i,
+            Some(Ok(i)) => {
+                println!("> index {} is out of bounds", i);
+                continue;
+            },
+            Some(Err(e)) => {
+                println!("> failed to parse index: {}", e);
+                continue;
+            },
+            None => continue,
+        };
+        match (command, pieces.next()) {
+            ("d", None) => {
+                new_events.remove(index);
+            },
+            ("e", Some(event_str)) => {
+                match parse::parse_event_line(event_str) {
+                    Ok(event_line) => {
+                        if let Some(new_event) = to_new_event(event_line, &session.time_zone) {
+                            new_events[index] = new_event;
+                        }
+                    },
+                    Err(e) => println!("> {}", e),
+                }
+            },
+            _ => println!("> unknown command {:?}", command_line),
+        }
+    }
+
+    for new_event in &new_events {
+        create_and_record_event(client, config, calendar, session, new_event).await;
+        println!("> created: {}", new_event.title);
+    }
+}
+
+/// Asks the user to pick one of the templates in the configuration.
+fn pick_template(config: &Config) -> Option<(&String, &EventTemplate)> {
+    if config.templates.is_empty() {
+        println!("> no templates are defined in config.toml");
+        return None;
+    }
+    let templates: Vec<(&String, &EventTemplate)> = config.templates.iter().collect();
+    loop {
+        println!("> Template? (or empty to cancel)");
+        for (i, (name, template)) in templates.iter().enumerate() {
+            println!("> {}. {} ({})", i, name, template.title);
+        }
+        let pick_line = read_stdin_line_trimmed();
+        if pick_line.is_empty() {
+            return None;
+        }
+        if let Some(named) = config.templates.get_key_value(&pick_line) {
+            return Some(named);
+        }
+        match pick_line.parse::<usize>() {
+            Ok(i) if i < templates.len() => return Some(templates[i]),
+            Ok(i) => println!("> index {} is out of bounds", i),
+            Err(_) => println!("> unknown template {:?}", pick_line),
+        }
+    }
+}
+
+/// Adds an event based on a template from the configuration, asking only for the times not given
+/// by the template.
+async fn add_event_from_template(client: &Client, config: &Config, calendar: &Calendar, session: &mut Session, date: &NaiveDate, day_events: &[ExistingEvent], template_name: &str) {
+    let (template_name, template) = if template_name.is_empty() {
+        match pick_template(config) {
+            Some(t) => t,
+            None => return,
+        }
+    } else {
+        match config.templates.get_key_value(template_name) {
+            Some(t) => t,
+            None => {
+                println!("> unknown template {:?}", template_name);
+                return;
+            },
+        }
+    };
+
+    let free_busy_status = match &template.free_busy_status {
+        None => FreeBusyStatus::Busy,
+        Some(fbs_str) => match parse::parse_free_busy_status(fbs_str) {
+            Some(fbs) => fbs,
+            None => {
+                println!("> template {:?} has an unknown free/busy status {:?}", template_name, fbs_str);
+                return;
+            },
+        },
+    };
+
+    let mut event_tz = session.time_zone;
+    let Some(start) = get_time(date, "Start time", None, session, &mut event_tz)
+        else { return };
+    let end = match template.duration_minutes {
+        Some(minutes) => start + TimeDelta::minutes(minutes.into()),
+        None => match get_time(date, "End time", Some(&start), session, &mut event_tz) {
+            Some(dt) => dt,
+            None => return,
+        },
+    };
+    let event_time = EventTime::Instants { start, end };
+    warn_about_event_time(date, session, day_events, &event_time);
+
+    let mut new_event = NewEvent::new(
+        event_time,
+        template.title.clone(),
+        template.location.clone(),
+        Some(free_busy_status),
+    );
+    new_event.reminder_minutes = if template.no_reminder {
+        None
+    } else {
+        template.reminder_minutes
+            .or_else(|| config.default_reminder_minutes(&calendar.display_name))
+    };
+    new_event.categories = template.categories.clone();
+
+    if !confirm_new_event(&new_event, &session.time_zone) {
+        return;
+    }
+
+    create_and_record_event(client, config, calendar, session, &new_event).await;
+}
+
+/// Lets the user pick one of the events on the given date and change its title, times, location,
+/// free/busy status or body.
+async fn edit_existing_event(client: &Client, config: &Config, session: &Session, date: &NaiveDate, day_events: &[ExistingEvent]) {
+    if day_events.is_empty() {
+        println!("> nothing scheduled on {} to edit", date.format("%Y-%m-%d"));
+        return;
+    }
+
+    let mut sorted_events: Vec<&ExistingEvent> = day_events.iter().collect();
+    sorted_events.sort_by_key(|e| (e.start_time, e.end_time));
+    let tz = session.time_zone;
+    for (i, event) in sorted_events.iter().enumerate() {
+        println!(
+            "> {:>2}. {}-{} [{}] {}",
+            i + 1,
+            event.start_time.with_timezone(&tz).format("%H:%M"),
+            event.end_time.with_timezone(&tz).format("%H:%M"),
+            event.free_busy_status.as_exchange_str(),
+            event.title,
+        );
+    }
+    let event = loop {
+        println!("> Which event? [number, empty to give up]");
+        let index_line = read_stdin_line_trimmed();
+        if index_line.is_empty() {
+            return;
+        }
+        match index_line.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= sorted_events.len() => break sorted_events[n - 1],
+            _ => println!("> expected a number between 1 and {}", sorted_events.len()),
+        }
+    };
+
+    println!("> Title? [empty keeps {:?}]", event.title);
+    let title_line = read_stdin_line_trimmed();
+    let title = if title_line.is_empty() { event.title.clone() } else { title_line };
+
+    let local_start = event.start_time.with_timezone(&tz);
+    let (start_time, end_time) = loop {
+        println!(
+            "> Times? [e.g. \"0930-1030\" or \"0930+45m\", empty keeps {}-{}]",
+            local_start.format("%H%M"),
+            event.end_time.with_timezone(&tz).format("%H%M"),
+        );
+        let times_line = read_stdin_line_trimmed();
+        if times_line.is_empty() {
+            break (event.start_time, event.end_time);
+        }
+        let range: String = times_line.split_whitespace().collect();
+        match parse::parse_event_line_range(&range) {
+            Ok(line_time) => {
+                if let Some(event_time) = event_time_from_line(&local_start.date_naive(), line_time, &tz) {
+                    break event_time.utc_bounds();
+                }
+            },
+            Err(e) => println!("> {}", e),
+        }
+    };
+
+    let location_description = match &event.location {
+        Some(l) => format!("empty keeps {:?}, \"-\" removes it", l),
+        None => "empty for none".to_owned(),
+    };
+    println!("> Location? [{}]", location_description);
+    let location_line = read_stdin_line_trimmed();
+    let location = match location_line.as_str() {
+        "" => event.location.clone(),
+        "-" => None,
+        _ => Some(location_line),
+    };
+
+    let free_busy_status = loop {
+        println!("> Free/busy state? [f=free, b=busy, t=tentative, o=out-of-office, e=elsewhere, n=no-data, empty keeps {}]", event.free_busy_status.as_exchange_str());
+        let fbs_line = read_stdin_line_trimmed();
+        if fbs_line.is_empty() {
+            break event.free_busy_status;
+        }
+        if let Some(fbs) = parse::parse_free_busy_status(&fbs_line) {
+            break fbs;
+        }
+    };
+
+    let mut changes = EventChanges::between(event, &title, start_time, end_time, location.as_deref(), free_busy_status);
+
+    let body_type = loop {
+        println!("> Replace the body? [t=text, h=HTML, empty keeps it]");
+        let format_line = read_stdin_line_trimmed();
+        match format_line.as_str() {
+            "" => break None,
+            "t" => break Some(BodyType::Text),
+            "h" => break Some(BodyType::Html),
+            _ => {},
+        }
+    };
+    if let Some(body_type) = body_type {
+        println!("> Body? (end with a line containing only \".\")");
+        let content = read_stdin_block();
+        changes.body = Some(EventBody::new(body_type, content));
+    }
+
+    if changes.is_empty() {
+        println!("> no changes");
+        return;
+    }
+
+    let updated_event = ExistingEvent::new(
+        event.item_id.clone(),
+        start_time,
+        end_time,
+        title,
+        location,
+        free_busy_status,
+        event.item_type,
+    );
+    println!("> before:");
+    agenda::print_agenda(date, &tz, std::slice::from_ref(event), None);
+    println!("> after:");
+    agenda::print_agenda(date, &tz, std::slice::from_ref(&updated_event), None);
+    if changes.body.is_some() {
+        println!("> (the body is replaced)");
+    }
+    loop {
+        println!("> Apply these changes? [y/n]");
+        let confirm_line = read_stdin_line_trimmed();
+        if confirm_line == "y" {
+            break;
+        } else if confirm_line == "n" {
+            return;
+        }
+    }
+
+    match apply_event_changes(client, config, &event.item_id, &changes).await {
+        Ok(_) => println!("> updated {:?}", updated_event.title),
+        Err(code) => println!("> failed to update {:?}: {}", event.title, code),
+    }
+}
+
+async fn add_event_loop(client: &mut Client, config: &Config, calendar: &Calendar, session: &mut Session, date: &NaiveDate) -> bool {
+    let day_events = get_agenda(client, config, calendar, date, &session.time_zone).await;
+    agenda::print_agenda(date, &session.time_zone, &day_events, None);
+
+    let mut ask_free_busy_state = false;
+    let mut ask_recurrence = false;
+    let mut all_day = false;
+    let mut ask_reminder = false;
+    let mut ask_details = false;
+    let mut ask_attendees = false;
+    loop {
+        println!("> Add an event on {}? [y=yes, n=no, or any of f=free/busy, a=all-day, r=recurring, m=reminder, d=details, i=invite attendees; or a one-line event like \"0900-1030 Sprint review @Room 4.12 !tentative\"; or p=paste a schedule; or \"t [NAME]\" to use a template; or e=edit an existing event] (or \"undo [N]\" to delete the last N created events)", date.format("%Y-%m-%d"));
+        let add_line = read_stdin_line_trimmed();
+
+        if add_line == "t" || add_line.starts_with("t ") {
+            let template_name = add_line[1..].trim();
+            add_event_from_template(client, config, calendar, session, date, &day_events, template_name).await;
+            return true;
+        }
+
+        if add_line == "e" {
+            edit_existing_event(client, config, session, date, &day_events).await;
+            return true;
+        }
+
+        if add_line == "p" {
+            paste_schedule(client, config, calendar, session, date, &day_events).await;
+            return true;
+        }
+
+        if parse::looks_like_event_line(&add_line) {
+            match parse::parse_event_line(&add_line) {
+                Ok(event_line) => {
+                    add_event_from_line(client, config, calendar, session, date, &day_events, event_line).await;
+                    return true;
+                },
+                Err(e) => println!("> {}", e),
+            }
+            continue;
+        }
+
+        if let Some(undo_result) = parse_undo_command(&add_line) {
+            match undo_result {
+                Ok(count) => undo_created_items(client, config, session, count).await,
+                Err(e) => println!("> {}", e),
+            }
+            continue;
+        }
+
+        if add_line == "y" {
+            // break out of this obstinate loop
+            break;
+        } else if add_line == "n" {
+            // exit the app
+            return false;
+        } else if !add_line.is_empty() && add_line.chars().all(|c| "farmdi".contains(c)) {
+            // f: user wants to add a special free-busy state
+            // a: user wants to add an all-day event
+            // r: user wants to add a recurring event
+            // m: user wants to set a reminder different from the calendar default
+            // d: user wants to add details (body, categories, sensitivity, importance)
+            // i: user wants to create a meeting and invite attendees
+            ask_free_busy_state = add_line.contains('f');
+            all_day = add_line.contains('a');
+            ask_recurrence = add_line.contains('r');
+            ask_reminder = add_line.contains('m');
+            ask_details = add_line.contains('d');
+            ask_attendees = add_line.contains('i');
+            break;
+        }
+
+        // otherwise, ask again
+    }
+
+    let event_time = if all_day {
+        let last_date = loop {
+            println!("> Last day? [yyyymmdd] (or empty for a single day or \"nvm\" to give up)");
+            let last_date_line = read_stdin_line_trimmed();
+            if last_date_line == "nvm" {
+                return true;
+            } else if last_date_line.is_empty() {
+                break *date;
+            }
+            match parse::parse_full_date(&last_date_line) {
+                Ok(d) if d < *date => println!("> last day is before the first day"),
+                Ok(d) => break d,
+                Err(e) => println!("> {}", e),
+            }
+        };
+        EventTime::AllDay { first_date: *date, last_date, time_zone: session.time_zone }
+    } else {
+        let mut event_tz = session.time_zone;
+        let start = match get_time(date, "Start time", None, session, &mut event_tz) {
+            None => return true,
+            Some(dt) => dt,
+        };
+        let end = match get_time(date, "End time", Some(&start), session, &mut event_tz) {
+            None => return true,
+            Some(dt) => dt,
+        };
+        EventTime::Instants { start, end }
+    };
+
+    warn_about_event_time(date, session, &day_events, &event_time);
+
+    println!("> Event name?");
+    let name = read_stdin_line_trimmed();
+
+    println!("> Location?");
+    let location_line = read_stdin_line_trimmed();
+    let location = if location_line.len() > 0 {
+        Some(location_line)
+    } else {
+        None
+    };
+
+    let free_busy_state = if ask_free_busy_state {
+        loop {
+            println!("> Free/busy state? [f=free, b=busy, t=tentative, o=out-of-office, e=elsewhere, n=no-data, q=quit]");
+            let fbs_line = read_stdin_line_trimmed();
+            match fbs_line.as_str() {
+                "f" => break FreeBusyStatus::Free,
+                "b" => break FreeBusyStatus::Busy,
+                "t" => break FreeBusyStatus::Tentative,
+                "o" => break FreeBusyStatus::OutOfOffice,
+                "e" => break FreeBusyStatus::WorkingElsewhere,
+                "n" => break FreeBusyStatus::NoData,
+                "q" => return true,
+                _ => {},
+            }
+        }
+    } else if all_day {
+        // like Outlook, don't block the whole day by default
+        FreeBusyStatus::Free
+    } else {
+        FreeBusyStatus::Busy
+    };
+
+    let recurrence = if ask_recurrence {
+        loop {
+            println!("> Recurrence? (e.g. \"weekly mon,wed until 20250630\", \"monthly every 2 last fri count 5\", \"yearly mar 15\"; or \"q\" to quit)");
+            let recurrence_line = read_stdin_line_trimmed();
+            if recurrence_line == "q" {
+                return true;
+            }
+            match parse::parse_recurrence(&recurrence_line, *date) {
+                Ok(r) => break Some(r),
+                Err(e) => println!("> {}", e),
+            }
+        }
+    } else {
+        None
+    };
+
+    let default_reminder_minutes = config.default_reminder_minutes(&calendar.display_name);
+    let reminder_minutes = if ask_reminder {
+        let default_description = match default_reminder_minutes {
+            Some(m) => format!("{} min", m),
+            None => "none".to_owned(),
+        };
+        loop {
+            println!("> Reminder? [minutes before start, \"none\" for no reminder, empty for the calendar default ({}), \"q\" to quit]", default_description);
+            let reminder_line = read_stdin_line_trimmed();
+            if reminder_line == "q" {
+                return true;
+            } else if reminder_line.is_empty() {
+                break default_reminder_minutes;
+            } else if reminder_line == "none" {
+                break None;
+            }
+            match reminder_line.parse() {
+                Ok(m) => break Some(m),
+                Err(e) => println!("> failed to parse {:?}: {}", reminder_line, e),
+            }
+        }
+    } else {
+        default_reminder_minutes
+    };
+
+    let mut new_event = NewEvent::new(
+        event_time,
+        name,
+        location,
+        Some(free_busy_state),
+    );
+    new_event.recurrence = recurrence;
+    new_event.reminder_minutes = reminder_minutes;
+
+    if ask_details && !ask_event_details(&mut new_event) {
+        return true;
+    }
+
+    if ask_attendees {
+        let attendee_kinds = [
+            (AttendeeKind::Required, "Required attendees"),
+            (AttendeeKind::Optional, "Optional attendees"),
+            (AttendeeKind::Resource, "Resources (e.g. rooms)"),
+        ];
+        for (kind, description) in attendee_kinds {
+            match ask_attendee_list(client, config, kind, description).await {
+                Some(attendees) => new_event.attendees.extend(attendees),
+                None => return true,
+            }
+        }
+        if !new_event.attendees.is_empty() {
+            println!("> invitations will be sent to {} attendee(s)", new_event.attendees.len());
+        }
+    }
+
+    create_and_record_event(client, config, calendar, session, &new_event).await;
+
+    true
+}
+
+async fn interaction_loop(mut client: Client, config: &Config, calendar: &Calendar) {
+    let mut session = Session::new();
+    println!("> run ID for this session: {}", session.run_id);
+    let mut previous_date: Option<NaiveDate> = None;
+    loop {
+        let date = loop {
+            println!("> Date? [today, tomorrow, mon, next fri, +3, mmdd, yyyymmdd or yyyy-mm-dd] (or \"next\" for the day after the previous one, \"nvm\" to end, \"undo [N]\" to delete the last N created events or \"tz\" to set the session time zone, currently {})", session.time_zone.name());
+            let date_line = read_stdin_line_trimmed();
+            if date_line == "nvm" {
+                return;
+            } else if date_line == "tz" {
+                if let Some(tz) = ask_time_zone() {
+                    session.time_zone = tz;
+                }
+                continue;
+            } else if date_line == "next" {
+                match previous_date {
+                    Some(pd) => break pd.checked_add_days(Days::new(1)).unwrap(),
+                    None => {
+                        println!("> no previous date; enter a date first");
+                        continue;
+                    },
+                }
+            }
+            if let Some(undo_result) = parse_undo_command(&date_line) {
+                match undo_result {
+                    Ok(count) => undo_created_items(&client, config, &mut session, count).await,
+                    Err(e) => println!("> {}", e),
+                }
+                continue;
+            }
+            let today = Utc::now().with_timezone(&session.time_zone).date_naive();
+            match parse::parse_date_input(&date_line, today) {
+                Ok(d) => break d,
+                Err(e) => println!("> {}", e),
+            }
+        };
+        previous_date = Some(date);
+        println!("> {}", date.format("%A, %Y-%m-%d"));
+
+        loop {
+            let keep_looping = add_event_loop(&mut client, config, calendar, &mut session, &date).await;
+            if !keep_looping {
+                break;
+            }
+        }
+    }
+}
+
+
+/// Finds the calendar with the given display name or qualified name (see
+/// [`Calendar::qualified_name`]) or, failing that, the only calendar whose display name or
+/// qualified name starts with the given prefix. All comparisons ignore case.
+fn find_calendar<'c>(calendars: &'c [Calendar], query: &str) -> Result<&'c Calendar, String> {
+    let lower_query = query.to_lowercase();
+    let names = |c: &Calendar| [c.display_name.to_lowercase(), c.qualified_name().to_lowercase()];
+
+    let exact_matches: Vec<&Calendar> = calendars.iter()
+        .filter(|c| names(c).contains(&lower_query))
+        .collect();
+    let matches = if exact_matches.is_empty() {
+        calendars.iter()
+            .filter(|c| names(c).iter().any(|n| n.starts_with(&lower_query)))
+            .collect()
+    } else {
+        exact_matches
+    };
+
+    match matches.as_slice() {
+        [] => Err(format!("no calendar matches {:?}", query)),
+        [calendar] => Ok(calendar),
+        multiple => {
+            let names: Vec<String> = multiple.iter()
+                .map(|c| c.qualified_name())
+                .collect();
+            Err(format!("{:?} matches multiple calendars: {}", query, names.join(", ")))
+        },
+    }
+}
+
+/// Outputs the calendars as a tree, grouped by owner.
+fn print_calendar_tree(calendars: &[Calendar]) {
+    let mut previous_owner = None;
+    for (i, calendar) in calendars.iter().enumerate() {
+        if i == 0 || previous_owner != Some(&calendar.owner) {
+            match &calendar.owner {
+                None => println!("> your calendars:"),
+                Some(owner) => println!("> calendars of {}:", owner),
+            }
+            previous_owner = Some(&calendar.owner);
+        }
+        println!("> {:>3}. {}{}", i, "  ".repeat(calendar.path.len()), calendar.display_name);
+    }
+}
+
+/// Asks the user to pick a calendar by index or name, offering the most recently used one first.
+fn pick_calendar<'c>(calendars: &'c [Calendar], last_calendar: Option<&str>) -> &'c Calendar {
+    let last_used = last_calendar
+        .and_then(|name| calendars.iter().find(|c| c.qualified_name() == name));
+    loop {
+        println!("> Pick a calendar:");
+        print_calendar_tree(calendars);
+        if let Some(calendar) = last_used {
+            println!("> (empty for the last used calendar, {})", calendar.qualified_name());
+        }
+
+        let buf = read_stdin_line_trimmed();
+        if buf.is_empty() {
+            if let Some(calendar) = last_used {
+                return calendar;
+            }
+            continue;
+        }
+
+        match buf.parse::<usize>() {
+            Ok(i) if i < calendars.len() => return &calendars[i],
+            Ok(i) => println!("index {} is out of bounds, try again", i),
+            Err(_) => match find_calendar(calendars, &buf) {
+                Ok(calendar) => return calendar,
+                Err(e) => println!("{}", e),
+            },
+        }
+    }
+}
+
+async fn journal_command(cli: &Cli, command: &JournalCommand) {
+    let entries = journal::read_entries(JOURNAL_PATH);
+    match command {
+        JournalCommand::List { run_id: None } => {
+            for run in journal::summarize_runs(&entries) {
+                println!("{}\t{} item(s)\t{}", run.run_id, run.item_count, run.calendar);
+            }
+        },
+        JournalCommand::List { run_id: Some(run_id) } => {
+            for entry in entries.iter().filter(|e| &e.run_id == run_id) {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    entry.start_time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    entry.end_time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    entry.calendar,
+                    entry.subject,
+                );
+            }
+        },
+        JournalCommand::Find { run_id } => {
+            let run_id = run_id.as_deref();
+
+            let config = load_config(&cli.config, cli.profile.as_deref());
+            let client = msswap::initial_auth(&config.exchange).await;
+            for calendar in get_calendars(&client, &config).await {
+                for event in get_tagged_items(&client, &config, &calendar, run_id).await {
+                    println!(
+                        "{}\t{}\t{}\t{}",
+                        event.start_time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                        event.end_time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                        calendar.display_name,
+                        event.title,
+                    );
+                }
+            }
+        },
+        JournalCommand::Revert { run_id } => {
+            let mut item_ids: Vec<ItemId> = entries.iter()
+                .filter(|e| &e.run_id == run_id)
+                .map(|e| e.to_item_id())
+                .collect();
+
+            let config = load_config(&cli.config, cli.profile.as_deref());
+            let client = msswap::initial_auth(&config.exchange).await;
+            if item_ids.is_empty() {
+                // maybe the run happened on a different machine; ask the server
+                println!("no journal entries for run {:?}; searching the calendars for tagged items", run_id);
+                for calendar in get_calendars(&client, &config).await {
+                    let events = get_tagged_items(&client, &config, &calendar, Some(run_id)).await;
+                    item_ids.extend(events.into_iter().map(|e| e.item_id));
+                }
+            }
+            if item_ids.is_empty() {
+                eprintln!("no items found for run {:?}", run_id);
+                std::process::exit(1);
+            }
+
+            let failure_count = delete_journaled_items(&client, &config, &item_ids, DeleteType::MoveToDeletedItems).await
+                .iter()
+                .filter(|o| o.is_err())
+                .count();
+            println!("deleted {} of {} item(s)", item_ids.len() - failure_count, item_ids.len());
+            if failure_count > 0 {
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+/// Determines the calendar for a non-interactive command from the command line or the
+/// configuration, exiting if there is none.
+fn calendar_for_command<'c>(cli: &Cli, config: &Config, calendars: &'c [Calendar]) -> &'c Calendar {
+    let Some(query) = cli.calendar.as_ref().or(config.default_calendar.as_ref()) else {
+        eprintln!("no calendar given; pass --calendar or set default_calendar in the configuration");
+        std::process::exit(1);
+    };
+    match find_calendar(calendars, query) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        },
+    }
+}
+
+/// Parses a date given on the command line, exiting if it is invalid.
+fn parse_date_arg(date_str: &str, tz: &Tz) -> NaiveDate {
+    let today = Utc::now().with_timezone(tz).date_naive();
+    match parse::parse_date_input(date_str, today) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        },
+    }
+}
+
+/// Creates the events described by the specifications, checking all of them before creating any.
+async fn add_events_from_specs(cli: &Cli, specs: &[EventSpec]) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+    let client = msswap::initial_auth(&config.exchange).await;
+    let calendars = get_calendars(&client, &config).await;
+    let calendar = calendar_for_command(cli, &config, &calendars);
+
+    let mut session = Session::new();
+    let today = Utc::now().with_timezone(&session.time_zone).date_naive();
+    let default_reminder_minutes = config.default_reminder_minutes(&calendar.display_name);
+
+    let mut new_events = Vec::with_capacity(specs.len());
+    let mut error_count = 0;
+    for (i, spec) in specs.iter().enumerate() {
+        match spec.to_new_event(today, session.time_zone, default_reminder_minutes) {
+            Ok(ne) => new_events.push(ne),
+            Err(e) => {
+                eprintln!("event {} ({:?}): {}", i + 1, spec.title, e);
+                error_count += 1;
+            },
+        }
+    }
+    if error_count > 0 {
+        eprintln!("no events created because of {} invalid event(s)", error_count);
+        std::process::exit(1);
+    }
+
+    for new_event in &new_events {
+        create_and_record_event(&client, &config, calendar, &mut session, new_event).await;
+        println!("created: {}", agenda::describe_new_event(new_event, &session.time_zone));
+    }
+    println!("run ID: {}", session.run_id);
+}
+
+/// Outputs the event as a tab-separated line of start, end, free/busy status, title and location.
+fn print_event_line(event: &ExistingEvent, tz: &Tz) {
+    println!(
+        "{}\t{}\t{}\t{}\t{}",
+        event.start_time.with_timezone(tz).format("%Y-%m-%d %H:%M"),
+        event.end_time.with_timezone(tz).format("%Y-%m-%d %H:%M"),
+        event.free_busy_status.as_exchange_str(),
+        event.title,
+        event.location.as_deref().unwrap_or(""),
+    );
+}
+
+/// Obtains the events in the filter's date range that pass its other criteria, sorted by time.
+///
+/// Exits if the dates are invalid.
+async fn get_filtered_events(client: &Client, config: &Config, calendar: &Calendar, filter: &EventFilter, tz: &Tz) -> Vec<ExistingEvent> {
+    let from_date = parse_date_arg(&filter.from, tz);
+    let to_date = match &filter.to {
+        Some(t) => parse_date_arg(t, tz),
+        None => from_date,
+    };
+    if to_date < from_date {
+        eprintln!("the last date is before the first date");
+        std::process::exit(1);
+    }
+
+    let (start, _) = agenda::day_bounds(&from_date, tz);
+    let (_, end) = agenda::day_bounds(&to_date, tz);
+    let mut events: Vec<ExistingEvent> = get_events(client, config, calendar, start, end).await
+        .into_iter()
+        .filter(|e| filter.matches(e))
+        .collect();
+    events.sort_by_key(|e| (e.start_time, e.end_time));
+    events
+}
+
+async fn list_command(cli: &Cli, from: Option<&str>, to: Option<&str>) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+    let tz = timezones::local_time_zone();
+    let from_date = match from {
+        Some(f) => parse_date_arg(f, &tz),
+        None => Utc::now().with_timezone(&tz).date_naive(),
+    };
+    let to_date = match to {
+        Some(t) => parse_date_arg(t, &tz),
+        None => from_date,
+    };
+    if to_date < from_date {
+        eprintln!("the last date is before the first date");
+        std::process::exit(1);
+    }
+
+    let client = msswap::initial_auth(&config.exchange).await;
+    let calendars = get_calendars(&client, &config).await;
+    let calendar = calendar_for_command(cli, &config, &calendars);
+
+    let (start, _) = agenda::day_bounds(&from_date, &tz);
+    let (_, end) = agenda::day_bounds(&to_date, &tz);
+    let mut events = get_events(&client, &config, calendar, start, end).await;
+    events.sort_by_key(|e| (e.start_time, e.end_time));
+    for event in &events {
+        print_event_line(event, &tz);
+    }
+}
+
+async fn delete_command(cli: &Cli, filter: &EventFilter, hard: bool, yes: bool) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+    let tz = timezones::local_time_zone();
+
+    let client = msswap::initial_auth(&config.exchange).await;
+    let calendars = get_calendars(&client, &config).await;
+    let calendar = calendar_for_command(cli, &config, &calendars);
+
+    let events = get_filtered_events(&client, &config, calendar, filter, &tz).await;
+    if events.is_empty() {
+        eprintln!("no matching events");
+        std::process::exit(1);
+    }
+    for event in &events {
+        print_event_line(event, &tz);
+    }
+    if !yes {
+        if hard {
+            println!("Permanently delete these {} event(s)? [y/n]", events.len());
+        } else {
+            println!("Move these {} event(s) to Deleted Items? [y/n]", events.len());
+        }
+        if read_stdin_line_trimmed() != "y" {
+            return;
+        }
+    }
+
+    let item_ids: Vec<ItemId> = events.into_iter()
+        .map(|e| e.item_id)
+        .collect();
+    let delete_type = if hard { DeleteType::HardDelete } else { DeleteType::MoveToDeletedItems };
+    let failure_count = delete_journaled_items(&client, &config, &item_ids, delete_type).await
+        .iter()
+        .filter(|o| o.is_err())
+        .count();
+    println!("deleted {} of {} item(s)", item_ids.len() - failure_count, item_ids.len());
+    if failure_count > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Replaces occurrences of recurring series by the recurring masters of their series (once per
+/// series).
+///
+/// Exits if a series cannot be obtained.
+async fn replace_occurrences_with_series(client: &Client, config: &Config, events: Vec<ExistingEvent>) -> Vec<ExistingEvent> {
+    let mut replaced: Vec<ExistingEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        if !event.item_type.is_occurrence() {
+            replaced.push(event);
+            continue;
+        }
+        let get_body = get_recurring_master(&event.item_id);
+        let get_bytes = post_ews(client, config, get_body, "recurring master").await;
+        let master = extract_got_event(get_bytes)
+            .unwrap_or_else(|code| {
+                eprintln!("failed to obtain the series of {:?}: {}", event.title, code);
+                std::process::exit(1);
+            });
+        if !replaced.iter().any(|r| r.item_id.id == master.item_id.id) {
+            replaced.push(master);
+        }
+    }
+    replaced
+}
+
+/// Outputs the times of the events before and after shifting them.
+fn print_shift_table(shifts: &[(ExistingEvent, DateTime<Utc>, DateTime<Utc>)], tz: &Tz) {
+    let format_time = |t: &DateTime<Utc>| t.with_timezone(tz).format("%Y-%m-%d %H:%M").to_string();
+    println!("{:<35}    {:<35}  title", "before", "after");
+    for (event, new_start, new_end) in shifts {
+        let series_marker = if event.item_type == CalendarItemType::RecurringMaster { " (series)" } else { "" };
+        println!(
+            "{} - {}    {} - {}  {}{}",
+            format_time(&event.start_time),
+            format_time(&event.end_time),
+            format_time(new_start),
+            format_time(new_end),
+            event.title,
+            series_marker,
+        );
+    }
+}
+
+async fn shift_command(cli: &Cli, filter: &EventFilter, by: &str, series: bool, yes: bool) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+    let tz = timezones::local_time_zone();
+    let offset = parse::parse_offset(by)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
+    let client = msswap::initial_auth(&config.exchange).await;
+    let calendars = get_calendars(&client, &config).await;
+    let calendar = calendar_for_command(cli, &config, &calendars);
+
+    let events = get_filtered_events(&client, &config, calendar, filter, &tz).await;
+    if events.is_empty() {
+        eprintln!("no matching events");
+        std::process::exit(1);
+    }
+
+    // the calendar view only contains occurrences; with --series, their masters are shifted
+    // instead
+    let targets = if series {
+        replace_occurrences_with_series(&client, &config, events).await
+    } else {
+        events
+    };
+
+    if !series && targets.iter().any(|t| t.item_type.is_occurrence()) {
+        println!("note: occurrences of recurring series are shifted individually; use --series to shift whole series");
+    }
+
+    let mut shifts: Vec<(ExistingEvent, DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(targets.len());
+    for event in targets {
+        let (Some(new_start), Some(new_end)) = (event.start_time.checked_add_signed(offset), event.end_time.checked_add_signed(offset)) else {
+            eprintln!("shifting {:?} by {} is out of range", event.title, by);
+            std::process::exit(1);
+        };
+        let moves_to_other_date = new_start.with_timezone(&tz).date_naive() != event.start_time.with_timezone(&tz).date_naive();
+        if event.item_type == CalendarItemType::RecurringMaster && moves_to_other_date {
+            // the days in the recurrence pattern (e.g. "every Monday") would stay the same
+            eprintln!("the series {:?} would start on another day, which would not match its recurrence pattern; shift its occurrences without --series instead", event.title);
+            std::process::exit(1);
+        }
+        shifts.push((event, new_start, new_end));
+    }
+    print_shift_table(&shifts, &tz);
+    if !yes {
+        println!("Shift these {} event(s)? [y/n]", shifts.len());
+        if read_stdin_line_trimmed() != "y" {
+            return;
+        }
+    }
+
+    let mut failure_count = 0;
+    for (event, new_start, new_end) in &shifts {
+        let changes = EventChanges {
+            start_time: Some(*new_start),
+            end_time: Some(*new_end),
+            ..EventChanges::default()
+        };
+        if let Err(code) = apply_event_changes(&client, &config, &event.item_id, &changes).await {
+            eprintln!("failed to shift {:?}: {}", event.title, code);
+            failure_count += 1;
+        }
+    }
+    println!("shifted {} of {} item(s)", shifts.len() - failure_count, shifts.len());
+    if failure_count > 0 {
+        std::process::exit(1);
+    }
+}
+
+async fn copy_command(cli: &Cli, filter: &EventFilter, target: &str, mode: TransferMode, series: bool, free_busy: Option<&str>, yes: bool) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+    let tz = timezones::local_time_zone();
+    let free_busy_status = free_busy.map(|fbs| parse::parse_free_busy_status(fbs)
+        .unwrap_or_else(|| {
+            eprintln!("unknown free/busy status {:?}", fbs);
+            std::process::exit(1);
+        }));
+
+    let client = msswap::initial_auth(&config.exchange).await;
+    let calendars = get_calendars(&client, &config).await;
+    let source_calendar = calendar_for_command(cli, &config, &calendars);
+    let target_calendar = find_calendar(&calendars, target)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+    if source_calendar.folder_id.id == target_calendar.folder_id.id {
+        eprintln!("the source and target calendars are the same");
+        std::process::exit(1);
+    }
+
+    let events = get_filtered_events(&client, &config, source_calendar, filter, &tz).await;
+    if events.is_empty() {
+        eprintln!("no matching events");
+        std::process::exit(1);
+    }
+    let occurrence_count = events.iter()
+        .filter(|e| e.item_type.is_occurrence())
+        .count();
+    let events = if series {
+        if occurrence_count > 0 {
+            println!("note: {} matching occurrence(s) are replaced by their whole series, including occurrences outside the date range", occurrence_count);
+        }
+        replace_occurrences_with_series(&client, &config, events).await
+    } else if occurrence_count > 0 {
+        // Exchange cannot copy or move single occurrences
+        eprintln!("{} of the matching events are occurrences of recurring series; pass --series to copy or move the whole series or narrow down the filters", occurrence_count);
+        std::process::exit(1);
+    } else {
+        events
+    };
+    for event in &events {
+        print_event_line(event, &tz);
+    }
+    if !yes {
+        let verb = match mode {
+            TransferMode::Copy => "Copy",
+            TransferMode::Move => "Move",
+        };
+        println!("{} these {} event(s) to {}? [y/n]", verb, events.len(), target_calendar.qualified_name());
+        if read_stdin_line_trimmed() != "y" {
+            return;
+        }
+    }
+
+    let item_ids: Vec<ItemId> = events.iter()
+        .map(|e| e.item_id.clone())
+        .collect();
+    let mut outcomes = Vec::with_capacity(item_ids.len());
+    for batch in item_ids.chunks(TRANSFER_BATCH_SIZE) {
+        let transfer_body = transfer_items(batch, &target_calendar.folder_id, mode);
+        let transfer_bytes = post_ews(&client, &config, transfer_body, mode.as_exchange_operation()).await;
+        outcomes.extend(extract_transfer_success(transfer_bytes, mode));
+    }
+
+    let mut transfer_count = 0;
+    let mut transfer_failure_count = 0;
+    let mut free_busy_failure_count = 0;
+    // moved items get new IDs; the journal must follow them so that they can still be reverted
+    let mut moved_item_ids = Vec::new();
+    let mut unknown_moved_item_ids = Vec::new();
+    for (i, event) in events.iter().enumerate() {
+        let new_item_id = match outcomes.get(i) {
+            Some(Ok(new_item_id)) => new_item_id.clone(),
+            Some(Err(code)) => {
+                eprintln!("failed to transfer {:?}: {}", event.title, code);
+                transfer_failure_count += 1;
+                continue;
+            },
+            None => {
+                eprintln!("no response for the transfer of {:?}", event.title);
+                transfer_failure_count += 1;
+                continue;
+            },
+        };
+        transfer_count += 1;
+        if mode == TransferMode::Move {
+            match &new_item_id {
+                Some(nii) => moved_item_ids.push((event.item_id.clone(), nii.clone())),
+                None => unknown_moved_item_ids.push(event.item_id.clone()),
+            }
+        }
+        let Some(free_busy_status) = free_busy_status
+            else { continue };
+        let Some(new_item_id) = new_item_id else {
+            eprintln!("cannot change the free/busy status of {:?}: the server did not return its new ID", event.title);
+            free_busy_failure_count += 1;
+            continue;
+        };
+        let changes = EventChanges {
+            free_busy_status: Some(free_busy_status),
+            ..EventChanges::default()
+        };
+        match apply_event_changes(&client, &config, &new_item_id, &changes).await {
+            Ok(updated_item_id) => {
+                // keep the journal's change key current
+                if let Some((_, moved_item_id)) = moved_item_ids.last_mut() {
+                    if moved_item_id.id == updated_item_id.id {
+                        *moved_item_id = updated_item_id;
+                    }
+                }
+            },
+            Err(code) => {
+                eprintln!("failed to change the free/busy status of {:?}: {}", event.title, code);
+                free_busy_failure_count += 1;
+            },
+        }
+    }
+    if !moved_item_ids.is_empty() {
+        journal::replace_item_ids(JOURNAL_PATH, &moved_item_ids, &target_calendar.display_name);
+    }
+    if !unknown_moved_item_ids.is_empty() {
+        // moved into another mailbox without a new ID; the journal can no longer refer to them
+        journal::remove_entries(JOURNAL_PATH, &unknown_moved_item_ids);
+    }
+    println!("transferred {} of {} event(s)", transfer_count, events.len());
+    if free_busy_failure_count > 0 {
+        println!("failed to change the free/busy status of {} transferred event(s)", free_busy_failure_count);
+    }
+    if transfer_failure_count > 0 || free_busy_failure_count > 0 {
+        std::process::exit(1);
+    }
+}
+
+async fn calendars_command(cli: &Cli) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+    let client = msswap::initial_auth(&config.exchange).await;
+    for calendar in get_calendars(&client, &config).await {
+        println!("{}", calendar.qualified_name());
+    }
+}
+
+/// Determines the calendar for an interactive command from the command line, the configuration
+/// or by asking the user, and remembers it as the last used one.
+fn select_calendar<'c>(cli: &Cli, config: &Config, calendars: &'c [Calendar]) -> &'c Calendar {
+    let mut state = state::read_state(STATE_PATH);
+
+    readline::add_completion_candidates(timezones::completion_names());
+    readline::add_completion_candidates(config.templates.keys().cloned());
+    readline::add_completion_candidates(calendars.iter().map(|c| c.qualified_name()));
+    readline::add_completion_candidates(calendars.iter().map(|c| c.display_name.clone()));
+    readline::add_completion_candidates(state.recent_locations.iter().cloned());
+
+    let calendar_query = cli.calendar.as_ref().or(config.default_calendar.as_ref());
+    let calendar = match calendar_query {
+        Some(query) => match find_calendar(calendars, query) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            },
+        },
+        None => pick_calendar(calendars, state.last_calendar.as_deref()),
+    };
+    println!("> using calendar {:?}", calendar.qualified_name());
+
+    state.last_calendar = Some(calendar.qualified_name());
+    state::write_state(STATE_PATH, &state);
+
+    calendar
+}
+
+async fn interactive_command(cli: &Cli) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+
+    let client = msswap::initial_auth(&config.exchange).await;
+
+    let calendars = get_calendars(&client, &config).await;
+    let calendar = select_calendar(cli, &config, &calendars);
+
+    interaction_loop(client, &config, calendar).await;
+}
+
+async fn tui_command(cli: &Cli) {
+    let config = load_config(&cli.config, cli.profile.as_deref());
+    let client = msswap::initial_auth(&config.exchange).await;
+    let calendars = get_calendars(&client, &config).await;
+    let calendar = select_calendar(cli, &config, &calendars);
+
+    let mut session = Session::new();
+    tui::run(&client, &config, calendar, &mut session).await;
+    println!("> run ID for this session: {}", session.run_id);
+}
+
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+
+    let cli = Cli::parse();
+    match &cli.command {
+        None | Some(Command::Interactive) => interactive_command(&cli).await,
+        Some(Command::Tui) => tui_command(&cli).await,
+        Some(Command::Add(spec)) => add_events_from_specs(&cli, std::slice::from_ref(spec)).await,
+        Some(Command::Import { file }) => {
+            let import_string = std::fs::read_to_string(file)
+                .unwrap_or_else(|e| panic!("failed to read {}: {}", file.display(), e));
+            let import_file: ImportFile = toml::from_str(&import_string)
+                .unwrap_or_else(|e| panic!("failed to parse {}: {}", file.display(), e));
+            add_events_from_specs(&cli, &import_file.event).await;
+        },
+        Some(Command::List { from, to }) => list_command(&cli, from.as_deref(), to.as_deref()).await,
+        Some(Command::Delete { filter, hard, yes }) => delete_command(&cli, filter, *hard, *yes).await,
+        Some(Command::Shift { filter, by, series, yes }) => shift_command(&cli, filter, by, *series, *yes).await,
+        Some(Command::Copy { filter, target, move_events, series, free_busy, yes }) => {
+            let mode = if *move_events { TransferMode::Move } else { TransferMode::Copy };
+            copy_command(&cli, filter, target, mode, *series, free_busy.as_deref(), *yes).await
+        },
+        Some(Command::Calendars) => calendars_command(&cli).await,
+        Some(Command::Journal(journal_command_args)) => journal_command(&cli, journal_command_args).await,
+    }
 }
@@ -1,4 +1,7 @@
-use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Offset, TimeDelta, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
 
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -19,45 +22,331 @@ impl FolderId {
 }
 
 
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ItemId {
+    pub id: String,
+    pub change_key: String,
+}
+impl ItemId {
+    pub fn new(
+        id: String,
+        change_key: String,
+    ) -> Self {
+        Self {
+            id,
+            change_key,
+        }
+    }
+}
+
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Calendar {
     pub folder_id: FolderId,
     pub display_name: String,
+
+    /// The e-mail address of the mailbox containing the calendar; `None` for the user's own.
+    pub owner: Option<String>,
+
+    pub parent_folder_id: Option<FolderId>,
+
+    /// The display names of the calendars containing this one, outermost first.
+    pub path: Vec<String>,
 }
 impl Calendar {
     pub fn new(
         folder_id: FolderId,
         display_name: String,
+        owner: Option<String>,
+        parent_folder_id: Option<FolderId>,
+        path: Vec<String>,
     ) -> Self {
         Self {
             folder_id,
             display_name,
+            owner,
+            parent_folder_id,
+            path,
+        }
+    }
+
+    /// Returns the name of the calendar including its path and, if it is not the user's own, its
+    /// owner, e.g. `Calendar/Team` or `alice@example.com:Calendar`.
+    pub fn qualified_name(&self) -> String {
+        let mut name = String::new();
+        if let Some(owner) = &self.owner {
+            name.push_str(owner);
+            name.push(':');
+        }
+        for ancestor in &self.path {
+            name.push_str(ancestor);
+            name.push('/');
+        }
+        name.push_str(&self.display_name);
+        name
+    }
+
+    /// Returns the display names of the containing calendars followed by that of this one.
+    pub fn full_path(&self) -> Vec<&str> {
+        self.path.iter()
+            .map(|p| p.as_str())
+            .chain(std::iter::once(self.display_name.as_str()))
+            .collect()
+    }
+}
+
+/// Fills in the paths of the calendars from their parent folders.
+///
+/// Only parents that are themselves among the calendars contribute to the path.
+pub fn assign_calendar_paths(calendars: &mut [Calendar]) {
+    let id_to_parent: HashMap<String, (String, Option<String>)> = calendars.iter()
+        .map(|c| (
+            c.folder_id.id.clone(),
+            (c.display_name.clone(), c.parent_folder_id.as_ref().map(|p| p.id.clone())),
+        ))
+        .collect();
+
+    for calendar in calendars.iter_mut() {
+        let mut path = Vec::new();
+        let mut parent_id = calendar.parent_folder_id.as_ref().map(|p| p.id.clone());
+        while let Some((parent_name, grandparent_id)) = parent_id.and_then(|pid| id_to_parent.get(&pid)) {
+            if path.len() >= id_to_parent.len() {
+                // cycle; bail out
+                break;
+            }
+            path.push(parent_name.clone());
+            parent_id = grandparent_id.clone();
+        }
+        path.reverse();
+        calendar.path = path;
+    }
+}
+
+/// Sorts the calendars by owner and then by their full paths, so that each calendar directly
+/// follows its parent and siblings.
+pub fn sort_calendars(calendars: &mut [Calendar]) {
+    calendars.sort_by(|a, b|
+        (&a.owner, a.full_path()).cmp(&(&b.owner, b.full_path()))
+    );
+}
+
+/// Returns the instant at which the given date starts in the given time zone.
+///
+/// If midnight is skipped by a daylight saving time change, the day starts when the skipped
+/// period ends.
+pub fn start_of_day<T: TimeZone>(date: NaiveDate, tz: &T) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    if let Some(start) = tz.from_local_datetime(&midnight).earliest() {
+        return start.with_timezone(&Utc);
+    }
+
+    // the skipped period ends at midnight according to the offset in effect before it
+    let offset_before = tz
+        .from_local_datetime(&(midnight - TimeDelta::hours(1)))
+        .earliest()
+        .expect("hour before a skipped midnight does not exist in time zone")
+        .offset()
+        .fix();
+    (midnight - TimeDelta::seconds(offset_before.local_minus_utc().into())).and_utc()
+}
+
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EventTime {
+    /// An event starting and ending at specific instants, each in the time zone in which it was
+    /// entered.
+    Instants { start: DateTime<Tz>, end: DateTime<Tz> },
+
+    /// An all-day event spanning the given dates (both inclusive) in the given time zone.
+    AllDay { first_date: NaiveDate, last_date: NaiveDate, time_zone: Tz },
+}
+impl EventTime {
+    /// Returns the instants at which the event starts and ends.
+    ///
+    /// All-day events start at the beginning of their first date and end at the beginning of the
+    /// date following their last date, in their time zone.
+    pub fn utc_bounds(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        match self {
+            Self::Instants { start, end } => (start.with_timezone(&Utc), end.with_timezone(&Utc)),
+            Self::AllDay { first_date, last_date, time_zone } => {
+                let after_last_date = last_date.checked_add_days(Days::new(1)).unwrap();
+                (start_of_day(*first_date, time_zone), start_of_day(after_last_date, time_zone))
+            },
         }
     }
+
+    pub fn start_time_zone(&self) -> Tz {
+        match self {
+            Self::Instants { start, .. } => start.timezone(),
+            Self::AllDay { time_zone, .. } => *time_zone,
+        }
+    }
+
+    pub fn end_time_zone(&self) -> Tz {
+        match self {
+            Self::Instants { end, .. } => end.timezone(),
+            Self::AllDay { time_zone, .. } => *time_zone,
+        }
+    }
+
+    pub fn is_all_day(&self) -> bool {
+        matches!(self, Self::AllDay { .. })
+    }
 }
 
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct NewEvent {
+    pub time: EventTime,
+    pub title: String,
+    pub location: Option<String>,
+    pub free_busy_status: FreeBusyStatus,
+    pub recurrence: Option<Recurrence>,
+
+    /// How many minutes before the start of the event to remind of it; `None` means no reminder.
+    pub reminder_minutes: Option<u32>,
+
+    pub body: Option<EventBody>,
+    pub categories: Vec<String>,
+    pub sensitivity: Sensitivity,
+    pub importance: Importance,
+
+    /// The attendees to invite; if empty, the event is an appointment instead of a meeting.
+    pub attendees: Vec<Attendee>,
+}
+impl NewEvent {
+    pub fn new(
+        time: EventTime,
+        title: String,
+        location: Option<String>,
+        free_busy_status: Option<FreeBusyStatus>,
+    ) -> Self {
+        Self {
+            time,
+            title,
+            location,
+            free_busy_status: free_busy_status.unwrap_or_default(),
+            recurrence: None,
+            reminder_minutes: None,
+            body: None,
+            categories: Vec::new(),
+            sensitivity: Sensitivity::default(),
+            importance: Importance::default(),
+            attendees: Vec::new(),
+        }
+    }
+}
+
+/// Changes to an existing event; fields that are `None` are left as they are.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct EventChanges {
+    pub title: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+
+    /// The new location; `Some(None)` removes the location.
+    pub location: Option<Option<String>>,
+
+    pub free_busy_status: Option<FreeBusyStatus>,
+
+    /// The new body, replacing the existing one.
+    pub body: Option<EventBody>,
+}
+impl EventChanges {
+    /// Returns the changes needed to turn the existing event into the given one.
+    pub fn between(existing: &ExistingEvent, title: &str, start_time: DateTime<Utc>, end_time: DateTime<Utc>, location: Option<&str>, free_busy_status: FreeBusyStatus) -> Self {
+        Self {
+            title: (existing.title != title).then(|| title.to_owned()),
+            start_time: (existing.start_time != start_time).then_some(start_time),
+            end_time: (existing.end_time != end_time).then_some(end_time),
+            location: (existing.location.as_deref() != location).then(|| location.map(|l| l.to_owned())),
+            free_busy_status: (existing.free_busy_status != free_busy_status).then_some(free_busy_status),
+            body: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ExistingEvent {
+    pub item_id: ItemId,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub title: String,
     pub location: Option<String>,
     pub free_busy_status: FreeBusyStatus,
+    pub item_type: CalendarItemType,
 }
-impl NewEvent {
+impl ExistingEvent {
     pub fn new(
+        item_id: ItemId,
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
         title: String,
         location: Option<String>,
-        free_busy_status: Option<FreeBusyStatus>,
+        free_busy_status: FreeBusyStatus,
+        item_type: CalendarItemType,
     ) -> Self {
         Self {
+            item_id,
             start_time,
             end_time,
             title,
             location,
-            free_busy_status: free_busy_status.unwrap_or_default(),
+            free_busy_status,
+            item_type,
+        }
+    }
+}
+
+/// The role of a calendar item with regard to recurrence.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum CalendarItemType {
+    /// A non-recurring event.
+    #[default] Single,
+
+    /// An unmodified occurrence of a recurring series.
+    Occurrence,
+
+    /// An occurrence of a recurring series that has been modified.
+    Exception,
+
+    /// The item defining a recurring series.
+    RecurringMaster,
+}
+impl CalendarItemType {
+    pub fn from_exchange_str(s: &str) -> Option<Self> {
+        match s {
+            "Single" => Some(Self::Single),
+            "Occurrence" => Some(Self::Occurrence),
+            "Exception" => Some(Self::Exception),
+            "RecurringMaster" => Some(Self::RecurringMaster),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the item is an occurrence (modified or not) of a recurring series.
+    pub fn is_occurrence(&self) -> bool {
+        matches!(self, Self::Occurrence | Self::Exception)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CreatedItem {
+    pub item_id: ItemId,
+    pub title: String,
+}
+impl CreatedItem {
+    pub fn new(
+        item_id: ItemId,
+        title: String,
+    ) -> Self {
+        Self {
+            item_id,
+            title,
         }
     }
 }
@@ -82,4 +371,324 @@ impl FreeBusyStatus {
             Self::NoData => "NoData",
         }
     }
+
+    pub fn from_exchange_str(s: &str) -> Option<Self> {
+        match s {
+            "Free" => Some(Self::Free),
+            "Tentative" => Some(Self::Tentative),
+            "Busy" => Some(Self::Busy),
+            "OOF" => Some(Self::OutOfOffice),
+            "WorkingElsewhere" => Some(Self::WorkingElsewhere),
+            "NoData" => Some(Self::NoData),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Mailbox {
+    pub name: Option<String>,
+    pub email_address: String,
+}
+impl Mailbox {
+    pub fn new(
+        name: Option<String>,
+        email_address: String,
+    ) -> Self {
+        Self {
+            name,
+            email_address,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Attendee {
+    pub mailbox: Mailbox,
+    pub kind: AttendeeKind,
+}
+impl Attendee {
+    pub fn new(
+        mailbox: Mailbox,
+        kind: AttendeeKind,
+    ) -> Self {
+        Self {
+            mailbox,
+            kind,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum AttendeeKind {
+    Required,
+    Optional,
+    Resource,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct EventBody {
+    pub body_type: BodyType,
+    pub content: String,
+}
+impl EventBody {
+    pub fn new(
+        body_type: BodyType,
+        content: String,
+    ) -> Self {
+        Self {
+            body_type,
+            content,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BodyType {
+    #[default] Text,
+    Html,
+}
+impl BodyType {
+    pub fn as_exchange_str(&self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Html => "HTML",
+        }
+    }
+}
+
+/// How items are deleted.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DeleteType {
+    /// Move the items to the Deleted Items folder, from which they can be restored.
+    #[default] MoveToDeletedItems,
+
+    /// Delete the items permanently.
+    HardDelete,
+}
+impl DeleteType {
+    pub fn as_exchange_str(&self) -> &'static str {
+        match self {
+            Self::MoveToDeletedItems => "MoveToDeletedItems",
+            Self::HardDelete => "HardDelete",
+        }
+    }
+}
+
+/// Whether items are copied or moved to another folder.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TransferMode {
+    #[default] Copy,
+    Move,
+}
+impl TransferMode {
+    /// Returns the name of the EWS operation, e.g. `CopyItem`.
+    pub fn as_exchange_operation(&self) -> &'static str {
+        match self {
+            Self::Copy => "CopyItem",
+            Self::Move => "MoveItem",
+        }
+    }
+}
+
+/// What Exchange should do if an item has been changed since its change key was obtained.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ConflictResolution {
+    /// Fail with `ErrorIrresolvableConflict`.
+    NeverOverwrite,
+
+    /// Apply the changes unless they touch properties that have been changed in the meantime.
+    #[default] AutoResolve,
+}
+impl ConflictResolution {
+    pub fn as_exchange_str(&self) -> &'static str {
+        match self {
+            Self::NeverOverwrite => "NeverOverwrite",
+            Self::AutoResolve => "AutoResolve",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Sensitivity {
+    #[default] Normal,
+    Personal,
+    Private,
+    Confidential,
+}
+impl Sensitivity {
+    pub fn as_exchange_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Personal => "Personal",
+            Self::Private => "Private",
+            Self::Confidential => "Confidential",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Importance {
+    Low,
+    #[default] Normal,
+    High,
+}
+impl Importance {
+    pub fn as_exchange_str(&self) -> &'static str {
+        match self {
+            Self::Low => "Low",
+            Self::Normal => "Normal",
+            Self::High => "High",
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Recurrence {
+    pub pattern: RecurrencePattern,
+    pub start_date: NaiveDate,
+    pub end: RecurrenceEnd,
+}
+impl Recurrence {
+    pub fn new(
+        pattern: RecurrencePattern,
+        start_date: NaiveDate,
+        end: RecurrenceEnd,
+    ) -> Self {
+        Self {
+            pattern,
+            start_date,
+            end,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RecurrencePattern {
+    /// Every `interval` days.
+    Daily { interval: u32 },
+
+    /// On the given days of every `interval`-th week.
+    Weekly { interval: u32, days_of_week: Vec<Weekday> },
+
+    /// On the given day of every `interval`-th month.
+    AbsoluteMonthly { interval: u32, day_of_month: u32 },
+
+    /// On e.g. the last Friday of every `interval`-th month.
+    RelativeMonthly { interval: u32, week: WeekOfMonth, day_of_week: Weekday },
+
+    /// On the given day of the given month (1 to 12) every year.
+    AbsoluteYearly { month: u32, day_of_month: u32 },
+
+    /// On e.g. the first Monday of the given month (1 to 12) every year.
+    RelativeYearly { month: u32, week: WeekOfMonth, day_of_week: Weekday },
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum WeekOfMonth {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Last,
+}
+impl WeekOfMonth {
+    pub fn as_exchange_str(&self) -> &'static str {
+        match self {
+            Self::First => "First",
+            Self::Second => "Second",
+            Self::Third => "Third",
+            Self::Fourth => "Fourth",
+            Self::Last => "Last",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RecurrenceEnd {
+    #[default] Never,
+    AfterOccurrences(u32),
+    OnDate(NaiveDate),
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use super::*;
+
+    #[test]
+    fn test_start_of_day() {
+        let cases = [
+            // ordinary days
+            (Tz::UTC, (2025, 6, 2), (2025, 6, 2, 0)),
+            (Tz::Europe__Vienna, (2025, 6, 2), (2025, 6, 1, 22)),
+            // days on which daylight saving time starts at 02:00
+            (Tz::Europe__Vienna, (2025, 3, 30), (2025, 3, 29, 23)),
+            // days on which daylight saving time starts at midnight, which is skipped
+            (Tz::America__Santiago, (2022, 9, 11), (2022, 9, 11, 4)),
+            (Tz::Asia__Beirut, (2022, 3, 27), (2022, 3, 26, 22)),
+        ];
+        for (tz, (y, m, d), (uy, um, ud, uh)) in cases {
+            let date = NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            let expected = Utc.with_ymd_and_hms(uy, um, ud, uh, 0, 0).unwrap();
+            assert_eq!(start_of_day(date, &tz), expected, "{} in {}", date, tz);
+        }
+    }
+
+    fn calendar(id: &str, name: &str, owner: Option<&str>, parent_id: Option<&str>) -> Calendar {
+        Calendar::new(
+            FolderId::new(id.to_owned(), String::new()),
+            name.to_owned(),
+            owner.map(|o| o.to_owned()),
+            parent_id.map(|p| FolderId::new(p.to_owned(), String::new())),
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_assign_calendar_paths() {
+        let mut calendars = vec![
+            calendar("x", "X", None, Some("a")),
+            calendar("a", "A", None, Some("root")),
+            calendar("y", "Y", None, Some("x")),
+            calendar("b", "B", None, Some("root")),
+            // parents that are not calendars do not contribute
+            calendar("z", "Z", None, Some("inbox")),
+            // cycles do not hang
+            calendar("c1", "C1", None, Some("c2")),
+            calendar("c2", "C2", None, Some("c1")),
+        ];
+        assign_calendar_paths(&mut calendars);
+
+        let qualified_names: Vec<String> = calendars.iter()
+            .take(5)
+            .map(|c| c.qualified_name())
+            .collect();
+        assert_eq!(qualified_names, ["A/X", "A", "A/X/Y", "B", "Z"]);
+        assert!(calendars[5].path.len() <= calendars.len());
+    }
+
+    #[test]
+    fn test_sort_calendars() {
+        let mut calendars = vec![
+            calendar("b", "B", None, None),
+            calendar("bob-cal", "Calendar", Some("bob@example.com"), None),
+            calendar("x", "X", None, Some("a")),
+            calendar("a", "A", None, None),
+            calendar("y", "Y", None, Some("x")),
+            calendar("w", "W", None, Some("a")),
+        ];
+        assign_calendar_paths(&mut calendars);
+        sort_calendars(&mut calendars);
+
+        let qualified_names: Vec<String> = calendars.iter()
+            .map(|c| c.qualified_name())
+            .collect();
+        assert_eq!(qualified_names, ["A", "A/W", "A/X", "A/X/Y", "B", "bob@example.com:Calendar"]);
+    }
 }
@@ -0,0 +1,928 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, TimeDelta, Weekday};
+
+use crate::model::{
+    FreeBusyStatus, Importance, Recurrence, RecurrenceEnd, RecurrencePattern, Sensitivity, WeekOfMonth,
+};
+
+
+const MONTH_ABBREVIATIONS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+
+pub(crate) fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s.to_lowercase().as_str() {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_week_of_month(s: &str) -> Option<WeekOfMonth> {
+    match s {
+        "first" | "1st" => Some(WeekOfMonth::First),
+        "second" | "2nd" => Some(WeekOfMonth::Second),
+        "third" | "3rd" => Some(WeekOfMonth::Third),
+        "fourth" | "4th" => Some(WeekOfMonth::Fourth),
+        "last" => Some(WeekOfMonth::Last),
+        _ => None,
+    }
+}
+
+/// Parses a month name (or its three-letter abbreviation) into its number (1 to 12).
+fn parse_month(s: &str) -> Option<u32> {
+    let lower = s.to_lowercase();
+    if lower.len() < 3 {
+        return None;
+    }
+    MONTH_ABBREVIATIONS.iter()
+        .position(|abbr| lower.starts_with(abbr))
+        .map(|i| u32::try_from(i).unwrap() + 1)
+}
+
+fn parse_day_of_month(s: &str) -> Result<u32, String> {
+    match s.parse() {
+        Ok(d) if (1..=31).contains(&d) => Ok(d),
+        _ => Err(format!("invalid day of month {:?}", s)),
+    }
+}
+
+fn parse_positive_number(s: &str, what: &str) -> Result<u32, String> {
+    match s.parse() {
+        Ok(0) => Err(format!("{} must not be 0", what)),
+        Ok(n) => Ok(n),
+        Err(e) => Err(format!("failed to parse {} {:?}: {}", what, s, e)),
+    }
+}
+
+/// Parses a comma-separated list of categories.
+pub(crate) fn parse_categories(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_owned())
+        .collect()
+}
+
+/// Parses a sensitivity by its name or the first letter of its name (`v` for private).
+pub(crate) fn parse_sensitivity(s: &str) -> Option<Sensitivity> {
+    match s.to_lowercase().as_str() {
+        "n" | "normal" => Some(Sensitivity::Normal),
+        "p" | "personal" => Some(Sensitivity::Personal),
+        "v" | "private" => Some(Sensitivity::Private),
+        "c" | "confidential" => Some(Sensitivity::Confidential),
+        _ => None,
+    }
+}
+
+/// Parses an importance by its name or the first letter of its name.
+pub(crate) fn parse_importance(s: &str) -> Option<Importance> {
+    match s.to_lowercase().as_str() {
+        "l" | "low" => Some(Importance::Low),
+        "n" | "normal" => Some(Importance::Normal),
+        "h" | "high" => Some(Importance::High),
+        _ => None,
+    }
+}
+
+/// Parses a date in the `yyyymmdd` or `yyyy-mm-dd` format.
+pub(crate) fn parse_full_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y%m%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+        .map_err(|e| format!("failed to parse date {:?}: {}", s, e))
+}
+
+/// Returns the first date on or after `from` that falls on the given weekday.
+fn upcoming_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_ahead = (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from.checked_add_days(Days::new(days_ahead.into())).unwrap()
+}
+
+/// Parses a date relative to `today`.
+///
+/// Accepted are `today`, `tomorrow`, weekday names (`fri` is the next Friday including today,
+/// `next fri` the one a week after that), a number of days from today (`+3`), `MMDD` in the
+/// current year, as well as `YYYYMMDD` and `YYYY-MM-DD`.
+pub(crate) fn parse_date_input(s: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let lower = s.trim().to_lowercase();
+    match lower.as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today.checked_add_days(Days::new(1)).unwrap()),
+        _ => {},
+    }
+
+    if let Some(weekday) = parse_weekday(&lower) {
+        return Ok(upcoming_weekday(today, weekday));
+    }
+    if let Some(weekday_str) = lower.strip_prefix("next ") {
+        let weekday = parse_weekday(weekday_str.trim())
+            .ok_or_else(|| format!("unknown weekday {:?}", weekday_str.trim()))?;
+        let upcoming = upcoming_weekday(today, weekday);
+        return Ok(upcoming.checked_add_days(Days::new(7)).unwrap());
+    }
+
+    if let Some(days_str) = lower.strip_prefix('+') {
+        let days: u64 = days_str.parse()
+            .map_err(|e| format!("failed to parse day count {:?}: {}", days_str, e))?;
+        return today.checked_add_days(Days::new(days))
+            .ok_or_else(|| format!("{} days from today is out of range", days));
+    }
+
+    if lower.len() == 4 && lower.chars().all(|c| c.is_ascii_digit()) {
+        let month: u32 = lower[..2].parse().unwrap();
+        let day: u32 = lower[2..].parse().unwrap();
+        return NaiveDate::from_ymd_opt(today.year(), month, day)
+            .ok_or_else(|| format!("{:?} is not a valid date in {}", s, today.year()));
+    }
+
+    parse_full_date(&lower)
+}
+
+/// A time entered at a time prompt.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum TimeSpec {
+    /// A time of day, on the date in question or the given number of days later.
+    TimeOfDay { time: NaiveTime, days_later: u32 },
+
+    /// A duration after the start of the event.
+    Duration(TimeDelta),
+}
+
+/// A line entered at a time prompt: a time specification, optionally followed by a time zone.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct TimeInput {
+    pub spec: TimeSpec,
+    pub time_zone_query: Option<String>,
+}
+
+/// Parses a time of day.
+///
+/// Accepted are `9` and `14` (full hours), `930` and `0930` (hours and minutes), `9:30` and
+/// `9.30`, as well as `9am`, `2pm` and `2:30pm`.
+pub(crate) fn parse_time_of_day(s: &str) -> Result<NaiveTime, String> {
+    let lower = s.trim().to_lowercase();
+    let (digits, is_pm) = if let Some(rest) = lower.strip_suffix("am") {
+        (rest.trim_end(), Some(false))
+    } else if let Some(rest) = lower.strip_suffix("pm") {
+        (rest.trim_end(), Some(true))
+    } else {
+        (lower.as_str(), None)
+    };
+
+    let parse_number = |n: &str| -> Result<u32, String> {
+        if n.is_empty() || !n.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("failed to parse time {:?}", s));
+        }
+        n.parse().map_err(|_| format!("{:?} is not a valid time of day", s))
+    };
+
+    let (mut hour, minute) = if let Some((h, m)) = digits.split_once([':', '.']) {
+        if m.len() != 2 {
+            return Err(format!("minutes in {:?} must have two digits", s));
+        }
+        (parse_number(h)?, parse_number(m)?)
+    } else {
+        // the hours and minutes are split by byte position
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("failed to parse time {:?}", s));
+        }
+        match digits.len() {
+            1 | 2 => (parse_number(digits)?, 0),
+            3 | 4 => {
+                let (h, m) = digits.split_at(digits.len() - 2);
+                (parse_number(h)?, parse_number(m)?)
+            },
+            _ => return Err(format!("failed to parse time {:?}", s)),
+        }
+    };
+
+    if let Some(is_pm) = is_pm {
+        if !(1..=12).contains(&hour) {
+            return Err(format!("hour in {:?} must be between 1 and 12 with am/pm", s));
+        }
+        hour %= 12;
+        if is_pm {
+            hour += 12;
+        }
+    }
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+        .ok_or_else(|| format!("{:?} is not a valid time of day", s))
+}
+
+/// Parses a duration such as `45m`, `45min`, `90` (minutes), `1h`, `1h30`, `1h30m`, `1:30` or `2d`.
+pub(crate) fn parse_duration(s: &str) -> Result<TimeDelta, String> {
+    let lower = s.trim().to_lowercase();
+    let invalid = || format!("failed to parse duration {:?}", s);
+    let too_long = || format!("duration {:?} is too long", s);
+
+    let total_minutes: i64 = if let Some((h, m)) = lower.split_once(':') {
+        let hours: i64 = h.parse().map_err(|_| invalid())?;
+        let minutes: i64 = m.parse().map_err(|_| invalid())?;
+        if m.len() != 2 || minutes >= 60 {
+            return Err(invalid());
+        }
+        hours.checked_mul(60)
+            .and_then(|hm| hm.checked_add(minutes))
+            .ok_or_else(too_long)?
+    } else {
+        let mut total = 0;
+        let mut rest = lower.as_str();
+        let mut previous_unit = None;
+        while !rest.is_empty() {
+            let digit_count = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digit_count == 0 {
+                return Err(invalid());
+            }
+            let number: i64 = rest[..digit_count].parse().map_err(|_| invalid())?;
+            rest = &rest[digit_count..];
+
+            let unit_length = rest.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+            let unit = &rest[..unit_length];
+            rest = &rest[unit_length..];
+
+            let minutes_per_unit = match (unit, previous_unit) {
+                ("d", None) => 24 * 60,
+                ("h", None) | ("h", Some("d")) => 60,
+                ("m", _) | ("min", _) => 1,
+                // a number without a unit after hours means minutes ("1h30"), otherwise minutes
+                // are the default anyway
+                ("", Some("h")) | ("", None) => 1,
+                _ => return Err(invalid()),
+            };
+            total = number.checked_mul(minutes_per_unit)
+                .and_then(|unit_total| unit_total.checked_add(total))
+                .ok_or_else(too_long)?;
+            previous_unit = Some(unit);
+        }
+        total
+    };
+
+    if total_minutes <= 0 {
+        return Err(format!("duration {:?} must be positive", s));
+    }
+    TimeDelta::try_minutes(total_minutes)
+        .ok_or_else(too_long)
+}
+
+/// Parses a signed offset such as `+1h`, `-30m` or `+7d` (see [`parse_duration`]).
+pub(crate) fn parse_offset(s: &str) -> Result<TimeDelta, String> {
+    let trimmed = s.trim();
+    if let Some(rest) = trimmed.strip_prefix('+') {
+        parse_duration(rest)
+    } else if let Some(rest) = trimmed.strip_prefix('-') {
+        parse_duration(rest).map(|d| -d)
+    } else {
+        Err(format!("offset {:?} must start with \"+\" or \"-\"", s))
+    }
+}
+
+/// Parses a day offset such as `+1d`.
+fn parse_day_offset(s: &str) -> Option<u32> {
+    s.strip_prefix('+')?
+        .strip_suffix('d')?
+        .parse().ok()
+}
+
+/// Parses the input at a time prompt.
+///
+/// The input consists of a time of day (see [`parse_time_of_day`]), optionally followed by a day
+/// offset like `+1d` for events ending on a later day, or a duration prefixed with a plus sign
+/// (e.g. `+45m`, see [`parse_duration`]). Either may be followed by a time zone query.
+pub(crate) fn parse_time_input(line: &str) -> Result<TimeInput, String> {
+    let mut tokens = line.split_whitespace().peekable();
+    let Some(time_token) = tokens.next()
+        else { return Err("no time given".to_owned()) };
+
+    let spec = if let Some(duration) = time_token.strip_prefix('+') {
+        TimeSpec::Duration(parse_duration(duration)?)
+    } else {
+        let (time_part, attached_offset) = match time_token.split_once('+') {
+            Some((tp, offset)) => (tp, Some(format!("+{}", offset))),
+            None => (time_token, None),
+        };
+        let time = parse_time_of_day(time_part)?;
+
+        // the day offset may be attached ("0200+1d") or separate ("0200 +1d")
+        let offset_token = match attached_offset {
+            Some(o) => Some(o),
+            None => tokens.next_if(|t| t.starts_with('+')).map(|t| t.to_owned()),
+        };
+        let days_later = match offset_token {
+            Some(o) => parse_day_offset(&o)
+                .ok_or_else(|| format!("failed to parse day offset {:?}; expected e.g. \"+1d\"", o))?,
+            None => 0,
+        };
+        TimeSpec::TimeOfDay { time, days_later }
+    };
+
+    let rest: Vec<&str> = tokens.collect();
+    let time_zone_query = if rest.is_empty() {
+        None
+    } else {
+        Some(rest.join(" "))
+    };
+
+    Ok(TimeInput {
+        spec,
+        time_zone_query,
+    })
+}
+
+/// The times of an event entered on a single line.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum EventLineTime {
+    /// The event lasts the whole day.
+    AllDay,
+
+    /// The event starts at the given time and ends as specified.
+    Range { start: NaiveTime, end: TimeSpec },
+}
+
+/// An event entered on a single line, see [`parse_event_line`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct EventLine {
+    pub time: EventLineTime,
+    pub title: String,
+    pub location: Option<String>,
+    pub free_busy_status: Option<FreeBusyStatus>,
+}
+
+/// Parses a free/busy status, either spelled out or abbreviated as at the free/busy prompt.
+pub(crate) fn parse_free_busy_status(s: &str) -> Option<FreeBusyStatus> {
+    match s.to_lowercase().as_str() {
+        "f" | "free" => Some(FreeBusyStatus::Free),
+        "b" | "busy" => Some(FreeBusyStatus::Busy),
+        "t" | "tentative" => Some(FreeBusyStatus::Tentative),
+        "o" | "oof" | "out-of-office" => Some(FreeBusyStatus::OutOfOffice),
+        "e" | "elsewhere" | "working-elsewhere" => Some(FreeBusyStatus::WorkingElsewhere),
+        "n" | "nodata" | "no-data" => Some(FreeBusyStatus::NoData),
+        _ => None,
+    }
+}
+
+/// Parses the time range at the beginning of an event line, e.g. `0900-1030`, `9am-2pm`,
+/// `2200-0100+1d` or `0900+45m`.
+pub(crate) fn parse_event_line_range(s: &str) -> Result<EventLineTime, String> {
+    if let Some((start_str, end_str)) = s.split_once('-') {
+        let start = parse_time_of_day(start_str)?;
+        let end = parse_time_input(end_str)?;
+        return Ok(EventLineTime::Range { start, end: end.spec });
+    }
+    if let Some((start_str, duration_str)) = s.split_once('+') {
+        let start = parse_time_of_day(start_str)?;
+        let duration = parse_duration(duration_str)?;
+        return Ok(EventLineTime::Range { start, end: TimeSpec::Duration(duration) });
+    }
+    Err(format!("failed to parse time range {:?}; expected e.g. \"0900-1030\" or \"0900+45m\"", s))
+}
+
+/// Returns whether the line looks like an attempt at a one-line event (see [`parse_event_line`]).
+pub(crate) fn looks_like_event_line(line: &str) -> bool {
+    let first_word = line.split_whitespace().next().unwrap_or("");
+    first_word.starts_with(|c: char| c.is_ascii_digit())
+        || first_word.eq_ignore_ascii_case("allday")
+}
+
+/// Parses an event entered on a single line.
+///
+/// The syntax is `TIMES TITLE [@LOCATION] [!STATUS]`, e.g. `0900-1030 Sprint review @Room 4.12
+/// !tentative`. `TIMES` is either a range (`0900-1030`, `9am-2pm`, `2200-0100+1d`), a start time
+/// with a duration (`0900+45m`) or `allday`. The location extends until the status or the end of
+/// the line; the status is one of the free/busy states (see [`parse_free_busy_status`]).
+pub(crate) fn parse_event_line(line: &str) -> Result<EventLine, String> {
+    let mut words = line.split_whitespace();
+    let Some(time_word) = words.next()
+        else { return Err("empty event line".to_owned()) };
+    let time = if time_word.eq_ignore_ascii_case("allday") {
+        EventLineTime::AllDay
+    } else {
+        parse_event_line_range(time_word)?
+    };
+
+    let mut title_words = Vec::new();
+    let mut location_words: Option<Vec<&str>> = None;
+    let mut free_busy_status = None;
+    for word in words {
+        if let Some(status_str) = word.strip_prefix('!') {
+            if free_busy_status.is_some() {
+                return Err("more than one free/busy status given".to_owned());
+            }
+            let status = parse_free_busy_status(status_str)
+                .ok_or_else(|| format!("unknown free/busy status {:?}", status_str))?;
+            free_busy_status = Some(status);
+        } else if free_busy_status.is_some() {
+            return Err(format!("unexpected {:?} after the free/busy status", word));
+        } else if let Some(location_start) = word.strip_prefix('@') {
+            if location_words.is_some() {
+                return Err("more than one location given".to_owned());
+            }
+            let mut lw = Vec::new();
+            if !location_start.is_empty() {
+                lw.push(location_start);
+            }
+            location_words = Some(lw);
+        } else if let Some(lw) = location_words.as_mut() {
+            lw.push(word);
+        } else {
+            title_words.push(word);
+        }
+    }
+
+    if title_words.is_empty() {
+        return Err("the event has no title".to_owned());
+    }
+    let location = location_words
+        .map(|lw| lw.join(" "))
+        .filter(|l| !l.is_empty());
+
+    Ok(EventLine {
+        time,
+        title: title_words.join(" "),
+        location,
+        free_busy_status,
+    })
+}
+
+/// Takes a time of day off the beginning of the string, returning it and the rest of the string.
+fn take_time_of_day(s: &str) -> Option<(NaiveTime, &str)> {
+    let digits_len = s.find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.'))
+        .unwrap_or(s.len());
+    let digits = s[..digits_len].trim_end_matches(['.', ':']);
+    if digits.is_empty() {
+        return None;
+    }
+    let mut rest = &s[digits.len()..];
+
+    // "9am", "9 am", "9 a.m."
+    let mut suffix = "";
+    let after_space = rest.trim_start();
+    for (marker, normalized) in [("a.m.", "am"), ("p.m.", "pm"), ("am", "am"), ("pm", "pm")] {
+        let matches_marker = after_space.get(..marker.len())
+            .is_some_and(|m| m.eq_ignore_ascii_case(marker));
+        if !matches_marker {
+            continue;
+        }
+        let followed_by_letter = after_space[marker.len()..]
+            .starts_with(|c: char| c.is_alphabetic());
+        if !followed_by_letter {
+            suffix = normalized;
+            rest = &after_space[marker.len()..];
+            break;
+        }
+    }
+
+    let time = parse_time_of_day(&format!("{}{}", digits, suffix)).ok()?;
+    Some((time, rest))
+}
+
+/// Heuristically parses a line of a pasted schedule, such as `09:00–09:45 Keynote (Hall A)` or
+/// `9am - 10am: Workshop @ Room 2`.
+///
+/// The line must start with a time range; the rest is the title, optionally followed by a
+/// location in parentheses or after an `@`. A range whose end is before its start is taken to end
+/// on the following day.
+pub(crate) fn parse_schedule_line(line: &str) -> Result<EventLine, String> {
+    let normalized: String = line.trim()
+        .chars()
+        .map(|c| if matches!(c, '\u{2010}'..='\u{2015}' | '\u{2212}') { '-' } else { c })
+        .collect();
+
+    let no_range = || format!("no time range found in {:?}", line.trim());
+    let (start, rest) = take_time_of_day(&normalized)
+        .ok_or_else(no_range)?;
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('-')
+        .or_else(|| rest.strip_prefix("to "))
+        .ok_or_else(no_range)?;
+    let (end_time, rest) = take_time_of_day(rest.trim_start())
+        .ok_or_else(no_range)?;
+    let days_later = if end_time < start { 1 } else { 0 };
+
+    let rest = rest.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, ':' | '|' | '-' | ',' | '\u{2022}'))
+        .trim_end();
+    let (title, location) = if let Some((title, location)) = rest.strip_suffix(')').and_then(|r| r.rsplit_once('(')) {
+        (title, Some(location.trim()))
+    } else if let Some((title, location)) = rest.rsplit_once('@') {
+        (title, Some(location.trim()))
+    } else {
+        (rest, None)
+    };
+
+    let title = title.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, ',' | '-' | '|'));
+    if title.is_empty() {
+        return Err(format!("no title found in {:?}", line.trim()));
+    }
+
+    Ok(EventLine {
+        time: EventLineTime::Range { start, end: TimeSpec::TimeOfDay { time: end_time, days_later } },
+        title: title.to_owned(),
+        location: location.filter(|l| !l.is_empty()).map(|l| l.to_owned()),
+        free_busy_status: None,
+    })
+}
+
+/// Parses a recurrence specification for events starting on the given date.
+///
+/// The specification consists of a pattern, optionally followed by `every N` (except for yearly
+/// patterns), optionally followed by an end:
+///
+/// * `daily`
+/// * `weekly mon,wed,fri`
+/// * `monthly 15` (on the 15th) or `monthly last fri`
+/// * `yearly mar 15` or `yearly first mon sep`
+///
+/// followed by one of `until yyyymmdd`, `count N` or `forever` (the default). For example,
+/// `weekly every 2 tue,thu count 10`.
+pub(crate) fn parse_recurrence(spec: &str, start_date: NaiveDate) -> Result<Recurrence, String> {
+    let lower_spec = spec.to_lowercase();
+    let tokens: Vec<&str> = lower_spec
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .collect();
+    let mut rest: &[&str] = &tokens;
+
+    let Some((&kind, after_kind)) = rest.split_first()
+        else { return Err("empty recurrence".to_owned()) };
+    rest = after_kind;
+
+    let mut interval = 1;
+    if kind != "yearly" {
+        if let ["every", n, after_every @ ..] = rest {
+            interval = parse_positive_number(n, "interval")?;
+            rest = after_every;
+        }
+    }
+
+    let pattern = match kind {
+        "daily" => RecurrencePattern::Daily { interval },
+        "weekly" => {
+            let mut days_of_week = Vec::new();
+            while let Some(day_of_week) = rest.first().and_then(|t| parse_weekday(t)) {
+                if !days_of_week.contains(&day_of_week) {
+                    days_of_week.push(day_of_week);
+                }
+                rest = &rest[1..];
+            }
+            if days_of_week.is_empty() {
+                return Err("weekly recurrence requires at least one day of the week".to_owned());
+            }
+            RecurrencePattern::Weekly { interval, days_of_week }
+        },
+        "monthly" => {
+            match rest {
+                [week, day_of_week, after @ ..] if parse_week_of_month(week).is_some() => {
+                    let week = parse_week_of_month(week).unwrap();
+                    let day_of_week = parse_weekday(day_of_week)
+                        .ok_or_else(|| format!("invalid day of the week {:?}", day_of_week))?;
+                    rest = after;
+                    RecurrencePattern::RelativeMonthly { interval, week, day_of_week }
+                },
+                [day_of_month, after @ ..] => {
+                    let day_of_month = parse_day_of_month(day_of_month)?;
+                    rest = after;
+                    RecurrencePattern::AbsoluteMonthly { interval, day_of_month }
+                },
+                [] => return Err("monthly recurrence requires a day".to_owned()),
+            }
+        },
+        "yearly" => {
+            match rest {
+                [week, day_of_week, month, after @ ..] if parse_week_of_month(week).is_some() => {
+                    let week = parse_week_of_month(week).unwrap();
+                    let day_of_week = parse_weekday(day_of_week)
+                        .ok_or_else(|| format!("invalid day of the week {:?}", day_of_week))?;
+                    let month = parse_month(month)
+                        .ok_or_else(|| format!("invalid month {:?}", month))?;
+                    rest = after;
+                    RecurrencePattern::RelativeYearly { month, week, day_of_week }
+                },
+                [month, day_of_month, after @ ..] => {
+                    let month = parse_month(month)
+                        .ok_or_else(|| format!("invalid month {:?}", month))?;
+                    let day_of_month = parse_day_of_month(day_of_month)?;
+                    rest = after;
+                    RecurrencePattern::AbsoluteYearly { month, day_of_month }
+                },
+                _ => return Err("yearly recurrence requires a month and a day".to_owned()),
+            }
+        },
+        other => return Err(format!("unknown recurrence kind {:?}; expected daily, weekly, monthly or yearly", other)),
+    };
+
+    let end = match rest {
+        [] | ["forever"] => RecurrenceEnd::Never,
+        ["count", n] => RecurrenceEnd::AfterOccurrences(parse_positive_number(n, "occurrence count")?),
+        ["until", date] => {
+            let end_date = parse_full_date(date)?;
+            if end_date < start_date {
+                return Err("recurrence ends before it starts".to_owned());
+            }
+            RecurrenceEnd::OnDate(end_date)
+        },
+        other => return Err(format!("unexpected {:?}; expected \"until yyyymmdd\", \"count N\" or \"forever\"", other.join(" "))),
+    };
+
+    Ok(Recurrence::new(pattern, start_date, end))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_date_input() {
+        // a Wednesday
+        let today = date(2025, 6, 4);
+        let valid = [
+            ("today", date(2025, 6, 4)),
+            ("Tomorrow", date(2025, 6, 5)),
+            ("wed", date(2025, 6, 4)),
+            ("fri", date(2025, 6, 6)),
+            ("Friday", date(2025, 6, 6)),
+            ("mon", date(2025, 6, 9)),
+            ("next wed", date(2025, 6, 11)),
+            ("next fri", date(2025, 6, 13)),
+            ("+0", date(2025, 6, 4)),
+            ("+3", date(2025, 6, 7)),
+            ("+30", date(2025, 7, 4)),
+            ("1225", date(2025, 12, 25)),
+            ("0101", date(2025, 1, 1)),
+            ("20260102", date(2026, 1, 2)),
+            ("2026-01-02", date(2026, 1, 2)),
+            (" today ", date(2025, 6, 4)),
+        ];
+        for (input, expected) in valid {
+            assert_eq!(parse_date_input(input, today), Ok(expected), "input {:?}", input);
+        }
+
+        let invalid = [
+            "", "yesterday", "next", "next month", "+", "+x", "-1", "1332", "0230", "123",
+            "2025-02-30", "+99999999999999999999", "+18446744073709551615",
+        ];
+        for input in invalid {
+            assert!(parse_date_input(input, today).is_err(), "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        let valid = [
+            ("9", time(9, 0)),
+            ("14", time(14, 0)),
+            ("930", time(9, 30)),
+            ("0930", time(9, 30)),
+            ("9:30", time(9, 30)),
+            ("9.30", time(9, 30)),
+            ("9am", time(9, 0)),
+            ("12am", time(0, 0)),
+            ("12pm", time(12, 0)),
+            ("2pm", time(14, 0)),
+            ("2:30 PM", time(14, 30)),
+            (" 2359 ", time(23, 59)),
+        ];
+        for (input, expected) in valid {
+            assert_eq!(parse_time_of_day(input), Ok(expected), "input {:?}", input);
+        }
+
+        let invalid = [
+            "", "am", "24", "2400", "960", "12345", "9:3", "9:300", "13pm", "0am", "x9", "9h",
+            "99999999999:00", "9:99999999999", "\u{e9}1", "\u{20ac}", "9\u{e9}", "\u{e9}:30",
+        ];
+        for input in invalid {
+            assert!(parse_time_of_day(input).is_err(), "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let valid = [
+            ("45m", TimeDelta::minutes(45)),
+            ("45min", TimeDelta::minutes(45)),
+            ("90", TimeDelta::minutes(90)),
+            ("1h", TimeDelta::hours(1)),
+            ("1h30", TimeDelta::minutes(90)),
+            ("1h30m", TimeDelta::minutes(90)),
+            ("1:30", TimeDelta::minutes(90)),
+            ("2d", TimeDelta::days(2)),
+            ("1d2h", TimeDelta::hours(26)),
+            ("1H", TimeDelta::hours(1)),
+        ];
+        for (input, expected) in valid {
+            assert_eq!(parse_duration(input), Ok(expected), "input {:?}", input);
+        }
+
+        let invalid = [
+            "", "0", "0m", "h", "1x", "1m1h", "30d1d", "1:3", "1:60", "-5m",
+            "999999999999999m", "99999999999999999999", "999999999999999d", "99999999999999999:00",
+        ];
+        for input in invalid {
+            assert!(parse_duration(input).is_err(), "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        let valid = [
+            ("+1h", TimeDelta::hours(1)),
+            ("-30m", TimeDelta::minutes(-30)),
+            ("+7d", TimeDelta::days(7)),
+            (" -1h30 ", TimeDelta::minutes(-90)),
+        ];
+        for (input, expected) in valid {
+            assert_eq!(parse_offset(input), Ok(expected), "input {:?}", input);
+        }
+
+        for input in ["", "1h", "+", "-0m", "+-1h", "+999999999999999m", "-999999999999999d"] {
+            assert!(parse_offset(input).is_err(), "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_time_input() {
+        let cases = [
+            ("0930", TimeSpec::TimeOfDay { time: time(9, 30), days_later: 0 }, None),
+            ("0200+1d", TimeSpec::TimeOfDay { time: time(2, 0), days_later: 1 }, None),
+            ("0200 +2d", TimeSpec::TimeOfDay { time: time(2, 0), days_later: 2 }, None),
+            ("+45m", TimeSpec::Duration(TimeDelta::minutes(45)), None),
+            ("9am New York", TimeSpec::TimeOfDay { time: time(9, 0), days_later: 0 }, Some("New York")),
+            ("+1h UTC", TimeSpec::Duration(TimeDelta::hours(1)), Some("UTC")),
+        ];
+        for (input, spec, time_zone_query) in cases {
+            let parsed = parse_time_input(input).unwrap_or_else(|e| panic!("input {:?}: {}", input, e));
+            assert_eq!(parsed.spec, spec, "input {:?}", input);
+            assert_eq!(parsed.time_zone_query.as_deref(), time_zone_query, "input {:?}", input);
+        }
+
+        for input in ["", "0200+1", "0200+xd", "0200 +99999999999d", "+999999999999999m"] {
+            assert!(parse_time_input(input).is_err(), "input {:?}", input);
+        }
+    }
+
+    fn range(start: NaiveTime, end: TimeSpec) -> EventLineTime {
+        EventLineTime::Range { start, end }
+    }
+
+    fn until(end_time: NaiveTime, days_later: u32) -> TimeSpec {
+        TimeSpec::TimeOfDay { time: end_time, days_later }
+    }
+
+    #[test]
+    fn test_parse_event_line() {
+        let valid = [
+            (
+                "0900-1030 Sprint review @Room 4.12 !tentative",
+                range(time(9, 0), until(time(10, 30), 0)), "Sprint review", Some("Room 4.12"), Some(FreeBusyStatus::Tentative),
+            ),
+            (
+                "9am-2pm Offsite",
+                range(time(9, 0), until(time(14, 0), 0)), "Offsite", None, None,
+            ),
+            (
+                "2200-0100+1d Night shift @ Plant !oof",
+                range(time(22, 0), until(time(1, 0), 1)), "Night shift", Some("Plant"), Some(FreeBusyStatus::OutOfOffice),
+            ),
+            (
+                "0900+45m Standup",
+                range(time(9, 0), TimeSpec::Duration(TimeDelta::minutes(45))), "Standup", None, None,
+            ),
+            (
+                "allday Conference !free",
+                EventLineTime::AllDay, "Conference", None, Some(FreeBusyStatus::Free),
+            ),
+            (
+                "ALLDAY Holiday @",
+                EventLineTime::AllDay, "Holiday", None, None,
+            ),
+        ];
+        for (input, time, title, location, free_busy_status) in valid {
+            let expected = EventLine {
+                time,
+                title: title.to_owned(),
+                location: location.map(|l| l.to_owned()),
+                free_busy_status,
+            };
+            assert_eq!(parse_event_line(input), Ok(expected), "input {:?}", input);
+        }
+
+        let invalid = [
+            "", "0900-1030", "0900-1030 @Room", "0900 Meeting", "0900-2500 Meeting", "x-1030 Meeting",
+            "0900-1030 Meeting !maybe", "0900-1030 Meeting !busy !free", "0900-1030 Meeting !busy later",
+            "0900-1030 Meeting @A @B", "0900+0m Meeting", "0900+999999999999999m Meeting",
+            "99999999999:00-1030 Meeting",
+        ];
+        for input in invalid {
+            assert!(parse_event_line(input).is_err(), "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_looks_like_event_line() {
+        for line in ["0900-1030 Meeting", "9am-10am x", "allday Holiday", "1"] {
+            assert!(looks_like_event_line(line), "line {:?}", line);
+        }
+        for line in ["", "y", "farm", "t Standup", "undo 2", "p"] {
+            assert!(!looks_like_event_line(line), "line {:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_parse_schedule_line() {
+        let valid = [
+            ("09:00\u{2013}09:45 Keynote (Hall A)", time(9, 0), until(time(9, 45), 0), "Keynote", Some("Hall A")),
+            ("9am - 10am: Workshop @ Room 2", time(9, 0), until(time(10, 0), 0), "Workshop", Some("Room 2")),
+            ("9 a.m. to 10 a.m. Breakfast", time(9, 0), until(time(10, 0), 0), "Breakfast", None),
+            ("14.30 - 15.00 | Coffee break", time(14, 30), until(time(15, 0), 0), "Coffee break", None),
+            ("  23:00-01:00 Party ()  ", time(23, 0), until(time(1, 0), 1), "Party", None),
+            ("1000-1100 \u{2022} Panel, Main stage", time(10, 0), until(time(11, 0), 0), "Panel, Main stage", None),
+        ];
+        for (input, start, end, title, location) in valid {
+            let expected = EventLine {
+                time: range(start, end),
+                title: title.to_owned(),
+                location: location.map(|l| l.to_owned()),
+                free_busy_status: None,
+            };
+            assert_eq!(parse_schedule_line(input), Ok(expected), "input {:?}", input);
+        }
+
+        let invalid = [
+            "", "Keynote 09:00-10:00", "09:00 Keynote", "09:00-10:00", "09:00-10:00 (Hall A)",
+            "09:00-25:00 Keynote", "99999999999:00-10:00 Keynote",
+        ];
+        for input in invalid {
+            assert!(parse_schedule_line(input).is_err(), "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_recurrence() {
+        use chrono::Weekday::{Fri, Mon, Thu, Tue, Wed};
+
+        let start_date = date(2025, 6, 2);
+        let valid = [
+            ("daily", RecurrencePattern::Daily { interval: 1 }, RecurrenceEnd::Never),
+            ("daily every 3 forever", RecurrencePattern::Daily { interval: 3 }, RecurrenceEnd::Never),
+            (
+                "weekly mon,wed until 20250630",
+                RecurrencePattern::Weekly { interval: 1, days_of_week: vec![Mon, Wed] },
+                RecurrenceEnd::OnDate(date(2025, 6, 30)),
+            ),
+            (
+                "Weekly every 2 tue, thu tue count 10",
+                RecurrencePattern::Weekly { interval: 2, days_of_week: vec![Tue, Thu] },
+                RecurrenceEnd::AfterOccurrences(10),
+            ),
+            (
+                "monthly 15",
+                RecurrencePattern::AbsoluteMonthly { interval: 1, day_of_month: 15 },
+                RecurrenceEnd::Never,
+            ),
+            (
+                "monthly every 2 last fri count 5",
+                RecurrencePattern::RelativeMonthly { interval: 2, week: WeekOfMonth::Last, day_of_week: Fri },
+                RecurrenceEnd::AfterOccurrences(5),
+            ),
+            (
+                "yearly mar 15",
+                RecurrencePattern::AbsoluteYearly { month: 3, day_of_month: 15 },
+                RecurrenceEnd::Never,
+            ),
+            (
+                "yearly first mon september until 2030-12-31",
+                RecurrencePattern::RelativeYearly { month: 9, week: WeekOfMonth::First, day_of_week: Mon },
+                RecurrenceEnd::OnDate(date(2030, 12, 31)),
+            ),
+        ];
+        for (input, pattern, end) in valid {
+            let expected = Recurrence::new(pattern, start_date, end);
+            assert_eq!(parse_recurrence(input, start_date), Ok(expected), "input {:?}", input);
+        }
+
+        let invalid = [
+            "", "hourly", "daily every 0", "daily every x", "daily every 99999999999", "weekly",
+            "weekly every 2", "monthly", "monthly 32", "monthly 0", "monthly last xyz", "yearly mar",
+            "yearly every 2 mar 15", "yearly xyz 15", "daily until 20250601", "daily until tomorrow",
+            "daily count 0", "daily count 99999999999", "daily forever count 3",
+        ];
+        for input in invalid {
+            assert!(parse_recurrence(input, start_date).is_err(), "input {:?}", input);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use rustyline::{Context, Editor, Helper};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+
+
+/// The file (relative to the working directory, like config.toml) in which the lines entered at
+/// prompts are kept across sessions.
+pub(crate) const HISTORY_PATH: &str = "history.txt";
+
+
+/// Completes the input at a prompt from a common pool of candidates (time zones, templates,
+/// calendars, locations).
+#[derive(Clone, Debug, Default)]
+struct PromptHelper {
+    candidates: BTreeSet<String>,
+}
+impl PromptHelper {
+    fn matching_candidates(&self, prefix: &str) -> Vec<Pair> {
+        let lower_prefix = prefix.to_lowercase();
+        self.candidates.iter()
+            .filter(|c| c.to_lowercase().starts_with(&lower_prefix))
+            .map(|c| Pair { display: c.clone(), replacement: c.clone() })
+            .collect()
+    }
+}
+impl Completer for PromptHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        // candidates such as locations may contain spaces, so try the whole line first, then the
+        // current word (e.g. a time zone after a time)
+        let before_cursor = &line[..pos];
+        let whole_line_matches = self.matching_candidates(before_cursor.trim_start());
+        if !before_cursor.trim().is_empty() && !whole_line_matches.is_empty() {
+            let start = before_cursor.len() - before_cursor.trim_start().len();
+            return Ok((start, whole_line_matches));
+        }
+
+        let word_start = before_cursor.rfind(char::is_whitespace)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &before_cursor[word_start..];
+        if word.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+        Ok((word_start, self.matching_candidates(word)))
+    }
+}
+impl Hinter for PromptHelper {
+    type Hint = String;
+}
+impl Highlighter for PromptHelper {}
+impl Validator for PromptHelper {}
+impl Helper for PromptHelper {}
+
+
+static EDITOR: Mutex<Option<Editor<PromptHelper, FileHistory>>> = Mutex::new(None);
+
+
+/// Runs the closure with the line editor, creating it (and loading the history) if necessary.
+fn with_editor<R, F: FnOnce(&mut Editor<PromptHelper, FileHistory>) -> R>(f: F) -> R {
+    let mut editor_guard = EDITOR.lock().unwrap();
+    let editor = editor_guard.get_or_insert_with(|| {
+        let mut editor = Editor::new()
+            .expect("failed to set up line editor");
+        editor.set_helper(Some(PromptHelper::default()));
+        // the history file does not exist on the first run
+        let _ = editor.load_history(HISTORY_PATH);
+        editor
+    });
+    f(editor)
+}
+
+/// Adds candidates for tab completion.
+pub(crate) fn add_completion_candidates<I: IntoIterator<Item = String>>(candidates: I) {
+    with_editor(|editor| {
+        let helper = editor.helper_mut().unwrap();
+        helper.candidates.extend(candidates);
+    });
+}
+
+/// Reads a line using the line editor, returning it with a trailing newline or an empty string at
+/// the end of input.
+///
+/// If `remember` is set, non-empty lines are added to the history.
+pub(crate) fn read_line(remember: bool) -> String {
+    with_editor(|editor| {
+        match editor.readline("") {
+            Ok(mut line) => {
+                if remember && !line.trim().is_empty() {
+                    editor.add_history_entry(line.as_str())
+                        .expect("failed to add line to history");
+                    if let Err(e) = editor.append_history(HISTORY_PATH) {
+                        eprintln!("warning: failed to save history: {}", e);
+                    }
+                }
+                line.push('\n');
+                line
+            },
+            Err(ReadlineError::Eof) => String::new(),
+            Err(ReadlineError::Interrupted) => std::process::exit(130),
+            Err(e) => panic!("failed to read line: {}", e),
+        }
+    })
+}
@@ -0,0 +1,288 @@
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Attendee, AttendeeKind, BodyType, EventBody, EventTime, FreeBusyStatus, Mailbox, NewEvent};
+use crate::parse::{self, EventLineTime, TimeSpec};
+use crate::timezones;
+
+
+/// A non-interactive description of an event, given either as command-line options (`add`) or as
+/// an `[[event]]` table in a TOML file (`import`).
+#[derive(Args, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EventSpec {
+    /// The (first) date of the event, e.g. "2025-06-02", "tomorrow" or "next fri".
+    #[arg(long)]
+    pub date: String,
+
+    /// The start time, e.g. "0930", "9:30" or "2pm".
+    #[arg(long, required_unless_present = "all_day")]
+    #[serde(default)] pub start: Option<String>,
+
+    /// The end time, e.g. "1030", "0200 +1d" or a duration such as "+45m".
+    #[arg(long, required_unless_present = "all_day")]
+    #[serde(default)] pub end: Option<String>,
+
+    /// Creates an all-day event instead of one with start and end times.
+    #[arg(long, conflicts_with_all = ["start", "end"])]
+    #[serde(default)] pub all_day: bool,
+
+    /// The last date of a multi-day all-day event.
+    #[arg(long, requires = "all_day")]
+    #[serde(default)] pub last_date: Option<String>,
+
+    /// The time zone of the event (IANA name, city or unambiguous abbreviation); the local time
+    /// zone if missing.
+    #[arg(long)]
+    #[serde(default)] pub time_zone: Option<String>,
+
+    /// The title (subject) of the event.
+    #[arg(long)]
+    pub title: String,
+
+    /// The location of the event.
+    #[arg(long)]
+    #[serde(default)] pub location: Option<String>,
+
+    /// The free/busy status, e.g. "busy", "tentative" or "oof".
+    #[arg(long)]
+    #[serde(default)] pub free_busy: Option<String>,
+
+    /// A recurrence specification, e.g. "weekly mon,wed until 20250630".
+    #[arg(long)]
+    #[serde(default)] pub recurrence: Option<String>,
+
+    /// Minutes before the start at which to remind of the event; the calendar's default if
+    /// missing.
+    #[arg(long, conflicts_with = "no_reminder")]
+    #[serde(default)] pub reminder_minutes: Option<u32>,
+
+    /// Sets no reminder, even if the calendar has a default.
+    #[arg(long)]
+    #[serde(default)] pub no_reminder: bool,
+
+    /// The description of the event.
+    #[arg(long)]
+    #[serde(default)] pub body: Option<String>,
+
+    /// Interprets the body as HTML instead of plain text.
+    #[arg(long, requires = "body")]
+    #[serde(default)] pub html: bool,
+
+    /// A category to assign; may be given multiple times.
+    #[arg(long = "category")]
+    #[serde(default)] pub categories: Vec<String>,
+
+    /// The sensitivity: "normal", "personal", "private" or "confidential".
+    #[arg(long)]
+    #[serde(default)] pub sensitivity: Option<String>,
+
+    /// The importance: "low", "normal" or "high".
+    #[arg(long)]
+    #[serde(default)] pub importance: Option<String>,
+
+    /// E-mail addresses of required attendees. Invitations are sent if any attendees are given.
+    #[arg(long = "attendee")]
+    #[serde(default)] pub attendees: Vec<String>,
+
+    /// E-mail addresses of optional attendees.
+    #[arg(long = "optional-attendee")]
+    #[serde(default)] pub optional_attendees: Vec<String>,
+
+    /// E-mail addresses of resources such as rooms.
+    #[arg(long = "resource")]
+    #[serde(default)] pub resources: Vec<String>,
+}
+impl EventSpec {
+    /// Converts the specification into a new event.
+    ///
+    /// Relative dates are interpreted relative to `today`. If the specification has no time zone,
+    /// `default_time_zone` is used; if it does not mention reminders, `default_reminder_minutes`.
+    pub fn to_new_event(&self, today: NaiveDate, default_time_zone: Tz, default_reminder_minutes: Option<u32>) -> Result<NewEvent, String> {
+        let date = parse::parse_date_input(&self.date, today)?;
+        let tz = match &self.time_zone {
+            None => default_time_zone,
+            Some(query) => unique_time_zone(query)?,
+        };
+
+        let time = if self.all_day {
+            let last_date = match &self.last_date {
+                None => date,
+                Some(ld) => parse::parse_date_input(ld, today)?,
+            };
+            if last_date < date {
+                return Err("the last date is before the first date".to_owned());
+            }
+            EventTime::AllDay { first_date: date, last_date, time_zone: tz }
+        } else {
+            let start_str = self.start.as_deref()
+                .ok_or("no start time given")?;
+            let end_str = self.end.as_deref()
+                .ok_or("no end time given")?;
+
+            let start = parse::parse_time_of_day(start_str)?;
+            let end_input = parse::parse_time_input(end_str)?;
+            if end_input.time_zone_query.is_some() {
+                return Err("give the time zone of the whole event instead of one for the end time".to_owned());
+            }
+            resolve_event_line_time(&date, EventLineTime::Range { start, end: end_input.spec }, &tz)?
+        };
+
+        let free_busy_status = match &self.free_busy {
+            Some(fbs) => parse::parse_free_busy_status(fbs)
+                .ok_or_else(|| format!("unknown free/busy status {:?}", fbs))?,
+            None if time.is_all_day() => FreeBusyStatus::Free,
+            None => FreeBusyStatus::Busy,
+        };
+
+        let mut new_event = NewEvent::new(
+            time,
+            self.title.clone(),
+            self.location.clone(),
+            Some(free_busy_status),
+        );
+
+        if let Some(recurrence) = &self.recurrence {
+            new_event.recurrence = Some(parse::parse_recurrence(recurrence, date)?);
+        }
+
+        new_event.reminder_minutes = if self.no_reminder {
+            None
+        } else {
+            self.reminder_minutes.or(default_reminder_minutes)
+        };
+
+        new_event.body = self.body.as_ref().map(|content| EventBody::new(
+            if self.html { BodyType::Html } else { BodyType::Text },
+            content.clone(),
+        ));
+        new_event.categories = self.categories.clone();
+        if let Some(sensitivity) = &self.sensitivity {
+            new_event.sensitivity = parse::parse_sensitivity(sensitivity)
+                .ok_or_else(|| format!("unknown sensitivity {:?}", sensitivity))?;
+        }
+        if let Some(importance) = &self.importance {
+            new_event.importance = parse::parse_importance(importance)
+                .ok_or_else(|| format!("unknown importance {:?}", importance))?;
+        }
+
+        let attendee_lists = [
+            (AttendeeKind::Required, &self.attendees),
+            (AttendeeKind::Optional, &self.optional_attendees),
+            (AttendeeKind::Resource, &self.resources),
+        ];
+        for (kind, addresses) in attendee_lists {
+            for address in addresses {
+                if !address.contains('@') {
+                    return Err(format!("attendee {:?} is not an e-mail address", address));
+                }
+                new_event.attendees.push(Attendee::new(
+                    Mailbox::new(None, address.clone()),
+                    kind,
+                ));
+            }
+        }
+
+        Ok(new_event)
+    }
+}
+
+
+/// A TOML file of events to import.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ImportFile {
+    #[serde(default)] pub event: Vec<EventSpec>,
+}
+
+
+/// Resolves a time zone query that must match exactly one time zone.
+fn unique_time_zone(query: &str) -> Result<Tz, String> {
+    let candidates = timezones::find_time_zones(query);
+    match candidates.as_slice() {
+        [] => Err(format!("unknown time zone {:?}", query)),
+        [tz] => Ok(*tz),
+        multiple => {
+            let names: Vec<&str> = multiple.iter()
+                .take(10)
+                .map(|tz| tz.name())
+                .collect();
+            Err(format!("time zone {:?} is ambiguous (e.g. {})", query, names.join(", ")))
+        },
+    }
+}
+
+/// Converts the times of a one-line event on the given date into event times in the given time
+/// zone, without asking the user about times that happen twice.
+pub(crate) fn resolve_event_line_time(date: &NaiveDate, line_time: EventLineTime, tz: &Tz) -> Result<EventTime, String> {
+    let (start_time, end_spec) = match line_time {
+        EventLineTime::AllDay => {
+            return Ok(EventTime::AllDay { first_date: *date, last_date: *date, time_zone: *tz });
+        },
+        EventLineTime::Range { start, end } => (start, end),
+    };
+
+    let start = resolve_local_time(date, &start_time, tz)?;
+    let end = match end_spec {
+        TimeSpec::Duration(duration) => start.checked_add_signed(duration)
+            .ok_or("the duration is too long")?,
+        TimeSpec::TimeOfDay { time, days_later } => {
+            let end_date = date.checked_add_days(Days::new(days_later.into()))
+                .ok_or("the day offset is too large")?;
+            resolve_local_time(&end_date, &time, tz)?
+        },
+    };
+    if end < start {
+        return Err("the end time is before the start time; append \"+1d\" if the event ends on the next day".to_owned());
+    }
+    Ok(EventTime::Instants { start, end })
+}
+
+/// Converts a local date and time into an instant, taking the earlier one if the time happens
+/// twice.
+fn resolve_local_time(date: &NaiveDate, time: &NaiveTime, tz: &Tz) -> Result<DateTime<Tz>, String> {
+    tz.from_local_datetime(&date.and_time(*time))
+        .earliest()
+        .ok_or_else(|| format!("{} {} does not exist in {}", date, time.format("%H:%M"), tz.name()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime, TimeDelta};
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_event_line_time_out_of_range() {
+        let date = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+        let start = NaiveTime::from_hms_opt(22, 0, 0).unwrap();
+        let ends = [
+            TimeSpec::TimeOfDay { time: NaiveTime::from_hms_opt(1, 0, 0).unwrap(), days_later: 4_000_000_000 },
+            TimeSpec::Duration(TimeDelta::try_minutes(i64::MAX / 60_000).unwrap()),
+        ];
+        for end in ends {
+            let line_time = EventLineTime::Range { start, end };
+            assert!(resolve_event_line_time(&date, line_time, &Tz::UTC).is_err(), "end {:?}", end);
+        }
+    }
+
+    #[test]
+    fn test_resolve_event_line_time() {
+        let date = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+        let start = NaiveTime::from_hms_opt(22, 0, 0).unwrap();
+        let line_time = EventLineTime::Range {
+            start,
+            end: TimeSpec::TimeOfDay { time: NaiveTime::from_hms_opt(1, 0, 0).unwrap(), days_later: 1 },
+        };
+        let EventTime::Instants { start, end } = resolve_event_line_time(&date, line_time, &Tz::UTC).unwrap()
+            else { panic!("expected instants") };
+        assert_eq!(end - start, TimeDelta::hours(3));
+
+        let line_time = EventLineTime::Range {
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: TimeSpec::TimeOfDay { time: NaiveTime::from_hms_opt(1, 0, 0).unwrap(), days_later: 0 },
+        };
+        assert!(resolve_event_line_time(&date, line_time, &Tz::UTC).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::ErrorKind;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+
+/// The maximum number of recently used locations to remember.
+const MAX_RECENT_LOCATIONS: usize = 100;
+
+
+/// The file (relative to the working directory, like config.toml) in which state is kept between
+/// runs.
+pub(crate) const STATE_PATH: &str = "state.json";
+
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct State {
+    /// The qualified name (see `Calendar::qualified_name`, e.g. "alice@example.com:Calendar/Team") of
+    /// the calendar used most recently.
+    #[serde(default)] pub last_calendar: Option<String>,
+
+    /// Recently used event locations, most recent first.
+    #[serde(default)] pub recent_locations: Vec<String>,
+}
+
+
+/// Reads the state; returns the default state if the file does not exist.
+pub(crate) fn read_state<P: AsRef<Path>>(path: P) -> State {
+    let state_string = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == ErrorKind::NotFound => return State::default(),
+        Err(e) => panic!("failed to read state: {}", e),
+    };
+    serde_json::from_str(&state_string)
+        .expect("failed to parse state")
+}
+
+pub(crate) fn write_state<P: AsRef<Path>>(path: P, state: &State) {
+    let state_string = serde_json::to_string_pretty(state)
+        .expect("failed to serialize state");
+    std::fs::write(path, state_string)
+        .expect("failed to write state");
+}
+
+/// Records a location as the most recently used one.
+pub(crate) fn remember_location<P: AsRef<Path>>(path: P, location: &str) {
+    let path = path.as_ref();
+    let mut state = read_state(path);
+    state.recent_locations.retain(|l| l != location);
+    state.recent_locations.insert(0, location.to_owned());
+    state.recent_locations.truncate(MAX_RECENT_LOCATIONS);
+    write_state(path, &state);
+}
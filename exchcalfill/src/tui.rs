@@ -0,0 +1,546 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use ratatui::{DefaultTerminal, Frame};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use reqwest::Client;
+
+use crate::{Session, apply_event_changes, create_and_record_event, delete_journaled_items, get_events};
+use crate::agenda;
+use crate::config::Config;
+use crate::model::{Calendar, DeleteType, EventChanges, ExistingEvent, FreeBusyStatus, NewEvent};
+use crate::parse;
+use crate::spec::resolve_event_line_time;
+
+
+/// The length of a row of the grid.
+const SLOT_MINUTES: u32 = 30;
+
+/// The number of rows of the grid per day.
+const SLOTS_PER_DAY: usize = (24 * 60 / SLOT_MINUTES) as usize;
+
+/// The width of the column showing the times of the rows.
+const TIME_COLUMN_WIDTH: u16 = 6;
+
+/// The slot selected at startup (08:00).
+const INITIAL_SLOT: usize = (8 * 60 / SLOT_MINUTES) as usize;
+
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum ViewKind {
+    Day,
+    Week,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Mode {
+    Browse,
+
+    /// Entering a new event in the one-line syntax.
+    Add { buffer: String },
+
+    /// Changing the selected event in the one-line syntax.
+    Edit { event: ExistingEvent, buffer: String },
+
+    /// Asking whether to delete the selected event.
+    ConfirmDelete { event: ExistingEvent },
+}
+
+/// What to do after a key has been handled.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Action {
+    Nothing,
+    Quit,
+    Reload,
+    Add { date: NaiveDate, line: String },
+    Edit { event: ExistingEvent, line: String },
+    Delete { event: ExistingEvent },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TuiState {
+    view: ViewKind,
+    selected_date: NaiveDate,
+    selected_slot: usize,
+    first_visible_slot: usize,
+    selected_event_index: usize,
+    events: Vec<ExistingEvent>,
+
+    /// The time range for which `events` were loaded.
+    loaded_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+
+    mode: Mode,
+    status: String,
+    time_zone: Tz,
+}
+impl TuiState {
+    fn visible_dates(&self) -> Vec<NaiveDate> {
+        match self.view {
+            ViewKind::Day => vec![self.selected_date],
+            ViewKind::Week => {
+                let monday = self.selected_date
+                    .checked_sub_days(Days::new(self.selected_date.weekday().num_days_from_monday().into()))
+                    .unwrap();
+                (0..7)
+                    .map(|i| monday.checked_add_days(Days::new(i)).unwrap())
+                    .collect()
+            },
+        }
+    }
+
+    /// Returns the time range covered by the visible dates.
+    fn visible_range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let dates = self.visible_dates();
+        let (start, _) = agenda::day_bounds(dates.first().unwrap(), &self.time_zone);
+        let (_, end) = agenda::day_bounds(dates.last().unwrap(), &self.time_zone);
+        (start, end)
+    }
+
+    fn slot_bounds(&self, date: &NaiveDate, slot: usize) -> (DateTime<Utc>, DateTime<Utc>) {
+        let slot_start_time = |s: usize| -> DateTime<Utc> {
+            if s >= SLOTS_PER_DAY {
+                return agenda::day_bounds(date, &self.time_zone).1;
+            }
+            // if the slot starts in a skipped period (e.g. a DST change), use the next valid slot start,
+            // or the end of the day if the rest of it is skipped
+            (s..SLOTS_PER_DAY)
+                .map(|t| date.and_time(NaiveTime::MIN + TimeDelta::minutes((t as u32 * SLOT_MINUTES).into())))
+                .find_map(|local_time| self.time_zone.from_local_datetime(&local_time).earliest())
+                .map(|start| start.to_utc())
+                .unwrap_or_else(|| agenda::day_bounds(date, &self.time_zone).1)
+        };
+        (slot_start_time(slot), slot_start_time(slot + 1))
+    }
+
+    /// Returns the events overlapping the given slot, ordered by start time.
+    fn events_in_slot(&self, date: &NaiveDate, slot: usize) -> Vec<&ExistingEvent> {
+        let (start, end) = self.slot_bounds(date, slot);
+        let mut slot_events: Vec<&ExistingEvent> = self.events.iter()
+            .filter(|e| agenda::overlaps(e, start, end))
+            .collect();
+        slot_events.sort_by_key(|e| (e.start_time, e.end_time));
+        slot_events
+    }
+
+    fn selected_event(&self) -> Option<&ExistingEvent> {
+        let slot_events = self.events_in_slot(&self.selected_date, self.selected_slot);
+        if slot_events.is_empty() {
+            None
+        } else {
+            Some(slot_events[self.selected_event_index % slot_events.len()])
+        }
+    }
+
+    fn move_date(&mut self, days: i64) {
+        let new_date = if days >= 0 {
+            self.selected_date.checked_add_days(Days::new(days.unsigned_abs()))
+        } else {
+            self.selected_date.checked_sub_days(Days::new(days.unsigned_abs()))
+        };
+        if let Some(nd) = new_date {
+            self.selected_date = nd;
+        }
+        self.selected_event_index = 0;
+    }
+
+    fn move_slot(&mut self, delta: isize) {
+        self.selected_slot = self.selected_slot
+            .saturating_add_signed(delta)
+            .min(SLOTS_PER_DAY - 1);
+        self.selected_event_index = 0;
+    }
+
+    /// Formats an event in the one-line syntax, for editing.
+    fn event_as_line(&self, event: &ExistingEvent) -> String {
+        let start = event.start_time.with_timezone(&self.time_zone);
+        let end = event.end_time.with_timezone(&self.time_zone);
+        let days_later = (end.date_naive() - start.date_naive()).num_days();
+        let mut line = format!("{}-{}", start.format("%H%M"), end.format("%H%M"));
+        if days_later > 0 {
+            line.push_str(&format!("+{}d", days_later));
+        }
+        line.push(' ');
+        line.push_str(&event.title);
+        if let Some(location) = &event.location {
+            line.push_str(" @");
+            line.push_str(location);
+        }
+        line.push_str(" !");
+        line.push_str(free_busy_keyword(event.free_busy_status));
+        line
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        match &mut self.mode {
+            Mode::Browse => {},
+            Mode::Add { buffer } | Mode::Edit { buffer, .. } => {
+                match key.code {
+                    KeyCode::Esc => self.mode = Mode::Browse,
+                    KeyCode::Backspace => {
+                        buffer.pop();
+                    },
+                    KeyCode::Char(c) => buffer.push(c),
+                    KeyCode::Enter => {
+                        let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                        return match mode {
+                            Mode::Add { buffer } => Action::Add { date: self.selected_date, line: buffer },
+                            Mode::Edit { event, buffer } => Action::Edit { event, line: buffer },
+                            _ => unreachable!(),
+                        };
+                    },
+                    _ => {},
+                }
+                return Action::Nothing;
+            },
+            Mode::ConfirmDelete { .. } => {
+                let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                if let (KeyCode::Char('y'), Mode::ConfirmDelete { event }) = (key.code, mode) {
+                    return Action::Delete { event };
+                }
+                self.status = "deletion cancelled".to_owned();
+                return Action::Nothing;
+            },
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Left | KeyCode::Char('h') => self.move_date(-1),
+            KeyCode::Right | KeyCode::Char('l') => self.move_date(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_slot(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_slot(1),
+            KeyCode::PageUp | KeyCode::Char('p') => {
+                self.move_date(if self.view == ViewKind::Week { -7 } else { -1 });
+            },
+            KeyCode::PageDown | KeyCode::Char('n') => {
+                self.move_date(if self.view == ViewKind::Week { 7 } else { 1 });
+            },
+            KeyCode::Char('w') => {
+                self.view = match self.view {
+                    ViewKind::Day => ViewKind::Week,
+                    ViewKind::Week => ViewKind::Day,
+                };
+            },
+            KeyCode::Char('t') => {
+                self.selected_date = Utc::now().with_timezone(&self.time_zone).date_naive();
+                self.selected_event_index = 0;
+            },
+            KeyCode::Char('r') => return Action::Reload,
+            KeyCode::Tab => self.selected_event_index += 1,
+            KeyCode::Char('a') | KeyCode::Enter => {
+                let start_minutes = self.selected_slot as u32 * SLOT_MINUTES;
+                let end_minutes = start_minutes + SLOT_MINUTES;
+                let end_suffix = if end_minutes >= 24 * 60 { "+1d" } else { "" };
+                self.mode = Mode::Add {
+                    buffer: format!(
+                        "{:02}{:02}-{:02}{:02}{} ",
+                        start_minutes / 60, start_minutes % 60,
+                        (end_minutes / 60) % 24, end_minutes % 60,
+                        end_suffix,
+                    ),
+                };
+            },
+            KeyCode::Char('e') => {
+                match self.selected_event().cloned() {
+                    Some(event) => {
+                        let buffer = self.event_as_line(&event);
+                        self.mode = Mode::Edit { event, buffer };
+                    },
+                    None => self.status = "no event selected".to_owned(),
+                }
+            },
+            KeyCode::Char('d') => {
+                match self.selected_event().cloned() {
+                    Some(event) => self.mode = Mode::ConfirmDelete { event },
+                    None => self.status = "no event selected".to_owned(),
+                }
+            },
+            _ => {},
+        }
+
+        // moving to another day or week requires its events
+        if self.loaded_range != Some(self.visible_range()) {
+            return Action::Reload;
+        }
+        Action::Nothing
+    }
+}
+
+
+/// Returns the keyword of the one-line syntax for the free/busy status.
+fn free_busy_keyword(status: FreeBusyStatus) -> &'static str {
+    match status {
+        FreeBusyStatus::Free => "free",
+        FreeBusyStatus::Tentative => "tentative",
+        FreeBusyStatus::Busy => "busy",
+        FreeBusyStatus::OutOfOffice => "oof",
+        FreeBusyStatus::WorkingElsewhere => "elsewhere",
+        FreeBusyStatus::NoData => "nodata",
+    }
+}
+
+fn free_busy_color(status: FreeBusyStatus) -> Color {
+    match status {
+        FreeBusyStatus::Free => Color::Green,
+        FreeBusyStatus::Tentative => Color::LightBlue,
+        FreeBusyStatus::Busy => Color::Blue,
+        FreeBusyStatus::OutOfOffice => Color::Magenta,
+        FreeBusyStatus::WorkingElsewhere => Color::Cyan,
+        FreeBusyStatus::NoData => Color::DarkGray,
+    }
+}
+
+/// Truncates or pads the text to exactly the given number of characters.
+fn fit(text: &str, width: usize) -> String {
+    let truncated: String = text.chars().take(width).collect();
+    format!("{:<width$}", truncated, width = width)
+}
+
+fn draw(frame: &mut Frame, state: &mut TuiState, calendar: &Calendar) {
+    let [header_area, grid_area, status_area, help_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+
+    let dates = state.visible_dates();
+    let header = format!(
+        " {} | {} | {}",
+        calendar.qualified_name(),
+        match state.view {
+            ViewKind::Day => state.selected_date.format("%A, %Y-%m-%d").to_string(),
+            ViewKind::Week => format!("week of {}", dates[0].format("%Y-%m-%d")),
+        },
+        state.time_zone.name(),
+    );
+    frame.render_widget(
+        Paragraph::new(header).style(Style::default().add_modifier(Modifier::BOLD)),
+        header_area,
+    );
+
+    draw_grid(frame, state, &dates, grid_area);
+
+    let status_line = match &state.mode {
+        Mode::Browse => Line::from(state.status.clone()),
+        Mode::Add { buffer } => Line::from(format!("new event on {}: {}_", state.selected_date.format("%Y-%m-%d"), buffer)),
+        Mode::Edit { buffer, .. } => Line::from(format!("edit: {}_", buffer)),
+        Mode::ConfirmDelete { event } => Line::from(format!("delete {:?}? [y/n]", event.title)),
+    };
+    frame.render_widget(Paragraph::new(status_line), status_area);
+
+    let help = match state.mode {
+        Mode::Browse => "arrows/hjkl move  n/p next/previous  w day/week  t today  Tab next event  a add  e edit  d delete  r reload  q quit",
+        Mode::Add { .. } | Mode::Edit { .. } => "HHMM-HHMM Title @Location !status   Enter save  Esc cancel",
+        Mode::ConfirmDelete { .. } => "y delete  any other key cancels",
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+        help_area,
+    );
+}
+
+fn draw_grid(frame: &mut Frame, state: &mut TuiState, dates: &[NaiveDate], area: Rect) {
+    let row_count = usize::from(area.height.saturating_sub(1)).max(1);
+    if state.selected_slot < state.first_visible_slot {
+        state.first_visible_slot = state.selected_slot;
+    } else if state.selected_slot >= state.first_visible_slot + row_count {
+        state.first_visible_slot = state.selected_slot + 1 - row_count;
+    }
+    state.first_visible_slot = state.first_visible_slot.min(SLOTS_PER_DAY.saturating_sub(row_count));
+
+    let column_width = usize::from(area.width.saturating_sub(TIME_COLUMN_WIDTH)) / dates.len();
+    let cell_width = column_width.saturating_sub(1);
+
+    let mut lines = Vec::with_capacity(row_count + 1);
+    let mut day_header = vec![Span::raw(" ".repeat(TIME_COLUMN_WIDTH.into()))];
+    for date in dates {
+        let style = if *date == state.selected_date {
+            Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        } else {
+            Style::default().add_modifier(Modifier::BOLD)
+        };
+        day_header.push(Span::styled(fit(&date.format("%a %m-%d").to_string(), cell_width), style));
+        day_header.push(Span::raw(" "));
+    }
+    lines.push(Line::from(day_header));
+
+    let selected_event_id = state.selected_event().map(|e| e.item_id.clone());
+    let last_slot = (state.first_visible_slot + row_count).min(SLOTS_PER_DAY);
+    for slot in state.first_visible_slot..last_slot {
+        let minutes = slot as u32 * SLOT_MINUTES;
+        let mut spans = vec![Span::styled(
+            fit(&format!("{:02}:{:02}", minutes / 60, minutes % 60), TIME_COLUMN_WIDTH.into()),
+            Style::default().fg(Color::DarkGray),
+        )];
+
+        for date in dates {
+            let slot_events = state.events_in_slot(date, slot);
+            let (slot_start, _) = state.slot_bounds(date, slot);
+            let is_selected = *date == state.selected_date && slot == state.selected_slot;
+
+            let (text, mut style) = match slot_events.first() {
+                None => (String::new(), Style::default()),
+                Some(first_event) => {
+                    let shown_event = slot_events.iter()
+                        .find(|e| Some(&e.item_id) == selected_event_id.as_ref())
+                        .unwrap_or(first_event);
+                    let starts_here = shown_event.start_time >= slot_start
+                        || slot == state.first_visible_slot
+                        || slot == 0;
+                    let more = if slot_events.len() > 1 { format!("+{} ", slot_events.len() - 1) } else { String::new() };
+                    let text = if starts_here {
+                        format!("{}{}", more, shown_event.title)
+                    } else {
+                        format!("{}\u{2502}", more)
+                    };
+                    let mut style = Style::default()
+                        .bg(free_busy_color(shown_event.free_busy_status))
+                        .fg(Color::White);
+                    if Some(&shown_event.item_id) == selected_event_id.as_ref() && *date == state.selected_date {
+                        style = style.add_modifier(Modifier::BOLD);
+                    }
+                    (text, style)
+                },
+            };
+            if is_selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(fit(&text, cell_width), style));
+            spans.push(Span::raw(" "));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+
+/// Loads the events of the visible dates.
+async fn reload(client: &Client, config: &Config, calendar: &Calendar, state: &mut TuiState) {
+    let (start, end) = state.visible_range();
+    state.events = get_events(client, config, calendar, start, end).await;
+    state.loaded_range = Some((start, end));
+}
+
+async fn perform(client: &Client, config: &Config, calendar: &Calendar, session: &mut Session, state: &mut TuiState, action: Action) {
+    match action {
+        Action::Nothing | Action::Quit | Action::Reload => {},
+        Action::Add { date, line } => {
+            let event_line = match parse::parse_event_line(&line) {
+                Ok(el) => el,
+                Err(e) => {
+                    state.status = e;
+                    return;
+                },
+            };
+            let event_time = match resolve_event_line_time(&date, event_line.time, &state.time_zone) {
+                Ok(et) => et,
+                Err(e) => {
+                    state.status = e;
+                    return;
+                },
+            };
+            let free_busy_status = event_line.free_busy_status.unwrap_or(if event_time.is_all_day() {
+                FreeBusyStatus::Free
+            } else {
+                FreeBusyStatus::Busy
+            });
+            let mut new_event = NewEvent::new(
+                event_time,
+                event_line.title,
+                event_line.location,
+                Some(free_busy_status),
+            );
+            new_event.reminder_minutes = config.default_reminder_minutes(&calendar.display_name);
+            create_and_record_event(client, config, calendar, session, &new_event).await;
+            state.status = format!("created: {}", agenda::describe_new_event(&new_event, &state.time_zone));
+        },
+        Action::Edit { event, line } => {
+            let event_line = match parse::parse_event_line(&line) {
+                Ok(el) => el,
+                Err(e) => {
+                    state.status = e;
+                    return;
+                },
+            };
+            let date = event.start_time.with_timezone(&state.time_zone).date_naive();
+            let event_time = match resolve_event_line_time(&date, event_line.time, &state.time_zone) {
+                Ok(et) => et,
+                Err(e) => {
+                    state.status = e;
+                    return;
+                },
+            };
+            let (start_time, end_time) = event_time.utc_bounds();
+            let changes = EventChanges::between(
+                &event,
+                &event_line.title,
+                start_time,
+                end_time,
+                event_line.location.as_deref(),
+                event_line.free_busy_status.unwrap_or(event.free_busy_status),
+            );
+            if changes.is_empty() {
+                state.status = "no changes".to_owned();
+                return;
+            }
+            state.status = match apply_event_changes(client, config, &event.item_id, &changes).await {
+                Ok(_) => format!("updated: {}", event_line.title),
+                Err(code) => format!("failed to update {:?}: {}", event.title, code),
+            };
+        },
+        Action::Delete { event } => {
+            let outcomes = delete_journaled_items(client, config, std::slice::from_ref(&event.item_id), DeleteType::MoveToDeletedItems).await;
+            state.status = if outcomes.iter().all(|o| o.is_ok()) {
+                format!("deleted: {}", event.title)
+            } else {
+                format!("failed to delete {:?}", event.title)
+            };
+        },
+    }
+}
+
+/// Shows the calendar in a full-screen day/week grid in which events can be added, edited and
+/// deleted.
+pub(crate) async fn run(client: &Client, config: &Config, calendar: &Calendar, session: &mut Session) {
+    let mut state = TuiState {
+        view: ViewKind::Week,
+        selected_date: Utc::now().with_timezone(&session.time_zone).date_naive(),
+        selected_slot: INITIAL_SLOT,
+        first_visible_slot: INITIAL_SLOT,
+        selected_event_index: 0,
+        events: Vec::new(),
+        loaded_range: None,
+        mode: Mode::Browse,
+        status: String::new(),
+        time_zone: session.time_zone,
+    };
+    reload(client, config, calendar, &mut state).await;
+
+    let mut terminal: DefaultTerminal = ratatui::init();
+    loop {
+        terminal.draw(|frame| draw(frame, &mut state, calendar))
+            .expect("failed to draw");
+
+        let key = match event::read().expect("failed to read terminal event") {
+            Event::Key(k) if k.kind == KeyEventKind::Press => k,
+            _ => continue,
+        };
+        let action = state.handle_key(key);
+        match action {
+            Action::Quit => break,
+            Action::Nothing => {},
+            Action::Reload => reload(client, config, calendar, &mut state).await,
+            other => {
+                perform(client, config, calendar, session, &mut state, other).await;
+                reload(client, config, calendar, &mut state).await;
+                // anything printed during the request garbles the screen
+                terminal.clear().expect("failed to clear terminal");
+            },
+        }
+    }
+    ratatui::restore();
+}
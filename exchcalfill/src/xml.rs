@@ -1,10 +1,193 @@
+use chrono::{DateTime, Utc, Weekday};
 use msswap::{EXCHANGE_MESSAGES_NS_URI, EXCHANGE_TYPES_NS_URI, SOAP_NS_URI, xot_ext::{NodeExt, XotExt}};
-use xot::{Node, Xot, output::xml::Parameters};
+use xot::{NamespaceId, Node, Xot, output::xml::Parameters};
 
-use crate::model::{Calendar, FolderId, NewEvent};
+use crate::model::{
+    AttendeeKind, Calendar, CalendarItemType, ConflictResolution, DeleteType, EventChanges, ExistingEvent, FolderId,
+    FreeBusyStatus, ItemId, Mailbox, NewEvent, Recurrence, RecurrenceEnd, RecurrencePattern, TransferMode,
+};
+use crate::timezones::windows_zone_id;
 
 
-pub(crate) fn search_for_calendars() -> Vec<u8> {
+/// The property set containing the extended properties with which we tag the items we create.
+const TAG_PROPERTY_SET_ID: &str = "5e4b9f0c-3a61-4c1d-8d2b-7a0f6e9c2d14";
+
+/// The name of the extended property containing the name of the tool that created the item.
+const TOOL_PROPERTY_NAME: &str = "Tool";
+
+/// The name of the extended property containing the batch (run) ID of the import that created
+/// the item.
+const BATCH_PROPERTY_NAME: &str = "BatchId";
+
+/// The value of the tool extended property.
+const TOOL_NAME: &str = "exchcalfill";
+
+/// The format of timestamps sent to Exchange.
+const EXCHANGE_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// The format of dates sent to Exchange.
+const EXCHANGE_DATE_FORMAT: &str = "%Y-%m-%d";
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// The number of items to request per FindItem page.
+const FIND_ITEM_PAGE_SIZE: usize = 256;
+
+
+fn weekday_exchange_str(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+fn create_recurrence(xot: &mut Xot, t_ns: NamespaceId, recurrence: &Recurrence) -> Node {
+    let recurrence_elem = xot.create_element_ns(t_ns, "Recurrence");
+
+    // the order of the children is prescribed by the schema
+    let (pattern_name, interval, days_of_week, week, day_of_month, month) = match &recurrence.pattern {
+        RecurrencePattern::Daily { interval }
+            => ("DailyRecurrence", Some(*interval), None, None, None, None),
+        RecurrencePattern::Weekly { interval, days_of_week }
+            => ("WeeklyRecurrence", Some(*interval), Some(days_of_week.clone()), None, None, None),
+        RecurrencePattern::AbsoluteMonthly { interval, day_of_month }
+            => ("AbsoluteMonthlyRecurrence", Some(*interval), None, None, Some(*day_of_month), None),
+        RecurrencePattern::RelativeMonthly { interval, week, day_of_week }
+            => ("RelativeMonthlyRecurrence", Some(*interval), Some(vec![*day_of_week]), Some(*week), None, None),
+        RecurrencePattern::AbsoluteYearly { month, day_of_month }
+            => ("AbsoluteYearlyRecurrence", None, None, None, Some(*day_of_month), Some(*month)),
+        RecurrencePattern::RelativeYearly { month, week, day_of_week }
+            => ("RelativeYearlyRecurrence", None, Some(vec![*day_of_week]), Some(*week), None, Some(*month)),
+    };
+
+    let pattern_elem = xot.create_element_ns(t_ns, pattern_name);
+    xot.append(recurrence_elem, pattern_elem).unwrap();
+
+    if let Some(interval) = interval {
+        let interval_elem = xot.create_text_element_ns(t_ns, "Interval", &interval.to_string());
+        xot.append(pattern_elem, interval_elem).unwrap();
+    }
+    if let Some(days_of_week) = days_of_week {
+        let days_string = days_of_week.iter()
+            .map(|d| weekday_exchange_str(*d))
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let days_elem = xot.create_text_element_ns(t_ns, "DaysOfWeek", &days_string);
+        xot.append(pattern_elem, days_elem).unwrap();
+    }
+    if let Some(week) = week {
+        let week_elem = xot.create_text_element_ns(t_ns, "DayOfWeekIndex", week.as_exchange_str());
+        xot.append(pattern_elem, week_elem).unwrap();
+    }
+    if let Some(day_of_month) = day_of_month {
+        let day_elem = xot.create_text_element_ns(t_ns, "DayOfMonth", &day_of_month.to_string());
+        xot.append(pattern_elem, day_elem).unwrap();
+    }
+    if let Some(month) = month {
+        let month_name = MONTH_NAMES[usize::try_from(month - 1).unwrap()];
+        let month_elem = xot.create_text_element_ns(t_ns, "Month", month_name);
+        xot.append(pattern_elem, month_elem).unwrap();
+    }
+
+    let range_name = match recurrence.end {
+        RecurrenceEnd::Never => "NoEndRecurrence",
+        RecurrenceEnd::AfterOccurrences(_) => "NumberedRecurrence",
+        RecurrenceEnd::OnDate(_) => "EndDateRecurrence",
+    };
+    let range_elem = xot.create_element_ns(t_ns, range_name);
+    xot.append(recurrence_elem, range_elem).unwrap();
+
+    let start_date_elem = xot.create_text_element_ns(
+        t_ns,
+        "StartDate",
+        &recurrence.start_date.format(EXCHANGE_DATE_FORMAT).to_string(),
+    );
+    xot.append(range_elem, start_date_elem).unwrap();
+
+    match recurrence.end {
+        RecurrenceEnd::Never => {},
+        RecurrenceEnd::AfterOccurrences(count) => {
+            let count_elem = xot.create_text_element_ns(t_ns, "NumberOfOccurrences", &count.to_string());
+            xot.append(range_elem, count_elem).unwrap();
+        },
+        RecurrenceEnd::OnDate(end_date) => {
+            let end_date_elem = xot.create_text_element_ns(
+                t_ns,
+                "EndDate",
+                &end_date.format(EXCHANGE_DATE_FORMAT).to_string(),
+            );
+            xot.append(range_elem, end_date_elem).unwrap();
+        },
+    }
+
+    recurrence_elem
+}
+
+fn create_tag_field_uri(xot: &mut Xot, t_ns: NamespaceId, property_name: &str) -> Node {
+    let ext_field_uri = xot.create_element_ns(t_ns, "ExtendedFieldURI");
+    xot.set_attribute_value(ext_field_uri, "PropertySetId", TAG_PROPERTY_SET_ID);
+    xot.set_attribute_value(ext_field_uri, "PropertyName", property_name);
+    xot.set_attribute_value(ext_field_uri, "PropertyType", "String");
+    ext_field_uri
+}
+
+fn create_tag_property(xot: &mut Xot, t_ns: NamespaceId, property_name: &str, value: &str) -> Node {
+    let ext_prop = xot.create_element_ns(t_ns, "ExtendedProperty");
+
+    let ext_field_uri = create_tag_field_uri(xot, t_ns, property_name);
+    xot.append(ext_prop, ext_field_uri).unwrap();
+
+    let value_elem = xot.create_text_element_ns(t_ns, "Value", value);
+    xot.append(ext_prop, value_elem).unwrap();
+
+    ext_prop
+}
+
+
+/// Appends a folder shape requesting the properties needed for [`Calendar`].
+fn append_calendar_folder_shape(xot: &mut Xot, m_ns: NamespaceId, t_ns: NamespaceId, parent: Node) {
+    let folder_shape = xot.create_element_ns(m_ns, "FolderShape");
+    xot.append(parent, folder_shape).unwrap();
+
+    let base_shape = xot.create_text_element_ns(t_ns, "BaseShape", "IdOnly");
+    xot.append(folder_shape, base_shape).unwrap();
+
+    let add_props = xot.create_element_ns(t_ns, "AdditionalProperties");
+    xot.append(folder_shape, add_props).unwrap();
+
+    for field in ["folder:DisplayName", "folder:FolderClass", "folder:ParentFolderId"] {
+        let field_uri = xot.create_element_ns(t_ns, "FieldURI");
+        xot.set_attribute_value(field_uri, "FieldURI", field);
+        xot.append(add_props, field_uri).unwrap();
+    }
+}
+
+/// Appends a distinguished folder ID, in the given mailbox if one is given.
+fn append_distinguished_folder_id(xot: &mut Xot, t_ns: NamespaceId, parent: Node, id: &str, mailbox: Option<&str>) {
+    let dist_folder_id = xot.create_element_ns(t_ns, "DistinguishedFolderId");
+    xot.set_attribute_value(dist_folder_id, "Id", id);
+    xot.append(parent, dist_folder_id).unwrap();
+
+    if let Some(email_address) = mailbox {
+        let mailbox_elem = xot.create_element_ns(t_ns, "Mailbox");
+        xot.append(dist_folder_id, mailbox_elem).unwrap();
+
+        let email_elem = xot.create_text_element_ns(t_ns, "EmailAddress", email_address);
+        xot.append(mailbox_elem, email_elem).unwrap();
+    }
+}
+
+/// Searches for calendar folders anywhere below the given distinguished folder (e.g.
+/// `msgfolderroot` or `calendar`), in the given mailbox or the user's own.
+pub(crate) fn search_for_calendars(root_folder: &str, mailbox: Option<&str>) -> Vec<u8> {
     let mut xot = Xot::new();
     let soap_doc = xot.create_exchange_soap_doc(true);
 
@@ -14,25 +197,10 @@ pub(crate) fn search_for_calendars() -> Vec<u8> {
     xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
 
     let find_folder = xot.create_element_ns(soap_doc.m_ns, "FindFolder");
-    xot.set_attribute_value(find_folder, "Traversal", "Shallow");
+    xot.set_attribute_value(find_folder, "Traversal", "Deep");
     xot.append(soap_doc.soap_body, find_folder).unwrap();
 
-    let folder_shape = xot.create_element_ns(soap_doc.m_ns, "FolderShape");
-    xot.append(find_folder, folder_shape).unwrap();
-
-    let base_shape = xot.create_text_element_ns(soap_doc.t_ns, "BaseShape", "IdOnly");
-    xot.append(folder_shape, base_shape).unwrap();
-
-    let add_props = xot.create_element_ns(soap_doc.t_ns, "AdditionalProperties");
-    xot.append(folder_shape, add_props).unwrap();
-
-    let name_field_uri = xot.create_element_ns(soap_doc.t_ns, "FieldURI");
-    xot.set_attribute_value(name_field_uri, "FieldURI", "folder:DisplayName");
-    xot.append(add_props, name_field_uri).unwrap();
-
-    let class_field_uri = xot.create_element_ns(soap_doc.t_ns, "FieldURI");
-    xot.set_attribute_value(class_field_uri, "FieldURI", "folder:FolderClass");
-    xot.append(add_props, class_field_uri).unwrap();
+    append_calendar_folder_shape(&mut xot, soap_doc.m_ns, soap_doc.t_ns, find_folder);
 
     let restriction = xot.create_element_ns(soap_doc.m_ns, "Restriction");
     xot.append(find_folder, restriction).unwrap();
@@ -54,9 +222,35 @@ pub(crate) fn search_for_calendars() -> Vec<u8> {
     let parent_folder_ids = xot.create_element_ns(soap_doc.m_ns, "ParentFolderIds");
     xot.append(find_folder, parent_folder_ids).unwrap();
 
-    let dist_folder_id = xot.create_element_ns(soap_doc.t_ns, "DistinguishedFolderId");
-    xot.set_attribute_value(dist_folder_id, "Id", "msgfolderroot");
-    xot.append(parent_folder_ids, dist_folder_id).unwrap();
+    append_distinguished_folder_id(&mut xot, soap_doc.t_ns, parent_folder_ids, root_folder, mailbox);
+
+    let mut buf = Vec::new();
+    xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
+        .expect("failed to serialize XML");
+    buf
+}
+
+/// Obtains the main calendar folder of the given mailbox.
+///
+/// Used for other people's mailboxes, where usually only the calendar (and not the whole
+/// mailbox) is shared.
+pub(crate) fn get_calendar_folder(mailbox: &str) -> Vec<u8> {
+    let mut xot = Xot::new();
+    let soap_doc = xot.create_exchange_soap_doc(true);
+
+    let req_version = xot.create_element_ns(soap_doc.t_ns, "RequestServerVersion");
+    xot.set_attribute_value(req_version, "Version", "Exchange2016");
+    xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
+
+    let get_folder = xot.create_element_ns(soap_doc.m_ns, "GetFolder");
+    xot.append(soap_doc.soap_body, get_folder).unwrap();
+
+    append_calendar_folder_shape(&mut xot, soap_doc.m_ns, soap_doc.t_ns, get_folder);
+
+    let folder_ids = xot.create_element_ns(soap_doc.m_ns, "FolderIds");
+    xot.append(get_folder, folder_ids).unwrap();
+
+    append_distinguished_folder_id(&mut xot, soap_doc.t_ns, folder_ids, "calendar", Some(mailbox));
 
     let mut buf = Vec::new();
     xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
@@ -64,7 +258,9 @@ pub(crate) fn search_for_calendars() -> Vec<u8> {
     buf
 }
 
-pub(crate) fn obtain_some_calendar_entry(folder_id: &FolderId) -> Vec<u8> {
+/// Requests the calendar items (including occurrences of recurring items) overlapping the given
+/// time range.
+pub(crate) fn obtain_calendar_view(folder_id: &FolderId, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<u8> {
     let mut xot = Xot::new();
     let soap_doc = xot.create_exchange_soap_doc(true);
 
@@ -73,23 +269,24 @@ pub(crate) fn obtain_some_calendar_entry(folder_id: &FolderId) -> Vec<u8> {
     xot.set_attribute_value(req_version, "Version", "Exchange2016");
     xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
 
-    let find_folder = xot.create_element_ns(soap_doc.m_ns, "FindItem");
-    xot.set_attribute_value(find_folder, "Traversal", "Shallow");
-    xot.append(soap_doc.soap_body, find_folder).unwrap();
+    let find_item = xot.create_element_ns(soap_doc.m_ns, "FindItem");
+    xot.set_attribute_value(find_item, "Traversal", "Shallow");
+    xot.append(soap_doc.soap_body, find_item).unwrap();
 
-    let item_shape = xot.create_element_ns(soap_doc.m_ns, "ItemShape");
-    xot.append(find_folder, item_shape).unwrap();
+    append_calendar_item_shape(&mut xot, soap_doc.m_ns, soap_doc.t_ns, find_item);
 
-    let base_shape = xot.create_text_element_ns(soap_doc.t_ns, "BaseShape", "AllProperties");
-    xot.append(item_shape, base_shape).unwrap();
+    let calendar_view = xot.create_element_ns(soap_doc.m_ns, "CalendarView");
+    xot.set_attribute_value(calendar_view, "StartDate", &start.format(EXCHANGE_TIMESTAMP_FORMAT).to_string());
+    xot.set_attribute_value(calendar_view, "EndDate", &end.format(EXCHANGE_TIMESTAMP_FORMAT).to_string());
+    xot.append(find_item, calendar_view).unwrap();
 
     let parent_folder_ids = xot.create_element_ns(soap_doc.m_ns, "ParentFolderIds");
-    xot.append(find_folder, parent_folder_ids).unwrap();
+    xot.append(find_item, parent_folder_ids).unwrap();
 
-    let dist_folder_id = xot.create_element_ns(soap_doc.t_ns, "FolderId");
-    xot.set_attribute_value(dist_folder_id, "Id", &folder_id.id);
-    xot.set_attribute_value(dist_folder_id, "ChangeKey", &folder_id.change_key);
-    xot.append(parent_folder_ids, dist_folder_id).unwrap();
+    let folder_id_elem = xot.create_element_ns(soap_doc.t_ns, "FolderId");
+    xot.set_attribute_value(folder_id_elem, "Id", &folder_id.id);
+    xot.set_attribute_value(folder_id_elem, "ChangeKey", &folder_id.change_key);
+    xot.append(parent_folder_ids, folder_id_elem).unwrap();
 
     let mut buf = Vec::new();
     xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
@@ -97,7 +294,56 @@ pub(crate) fn obtain_some_calendar_entry(folder_id: &FolderId) -> Vec<u8> {
     buf
 }
 
-pub(crate) fn extract_found_calendars(xml_bytes: Vec<u8>) -> Vec<Calendar> {
+/// Converts a t:CalendarFolder element into a calendar (with an empty path).
+fn calendar_from_node(xot: &mut Xot, calendar_node: Node, t_ns: NamespaceId, owner: Option<&str>) -> Calendar {
+    let folder_id_n = xot.add_name_ns("FolderId", t_ns);
+    let parent_folder_id_n = xot.add_name_ns("ParentFolderId", t_ns);
+    let display_name_n = xot.add_name_ns("DisplayName", t_ns);
+    let id_n = xot.add_name("Id");
+    let change_key_n = xot.add_name("ChangeKey");
+    let xot: &Xot = xot;
+
+    let folder_id_from_elem = |elem: Node| {
+        let id = xot.get_attribute(elem, id_n)
+            .expect("no Id attribute");
+        let change_key = xot.get_attribute(elem, change_key_n)
+            .expect("no ChangeKey attribute");
+        FolderId::new(
+            id.to_owned(),
+            change_key.to_owned(),
+        )
+    };
+
+    let folder_id_elem = calendar_node
+        .first_child_element_named(xot, folder_id_n)
+        .expect("no t:FolderId");
+    let folder_id_obj = folder_id_from_elem(folder_id_elem);
+
+    let parent_folder_id_obj = calendar_node
+        .first_child_element_named(xot, parent_folder_id_n)
+        .map(folder_id_from_elem);
+
+    let display_name_str = calendar_node
+        .first_child_element_named(xot, display_name_n)
+        .expect("no t:DisplayName")
+        .child_text(xot)
+        .expect("t:DisplayName does not only have text children");
+
+    Calendar::new(
+        folder_id_obj,
+        display_name_str,
+        owner.map(|o| o.to_owned()),
+        parent_folder_id_obj,
+        Vec::new(),
+    )
+}
+
+/// Extracts the calendars from a FindFolder (from [`search_for_calendars`]) or GetFolder (from
+/// [`get_calendar_folder`]) response, marking them as owned by the given mailbox.
+///
+/// Returns the response code if the request failed, e.g. because the mailbox is not shared with
+/// the user.
+pub(crate) fn extract_found_calendars(xml_bytes: Vec<u8>, owner: Option<&str>) -> Result<Vec<Calendar>, String> {
     let mut xot = Xot::new();
     let doc = xot.parse_bytes(&xml_bytes)
         .expect("failed to parse XML");
@@ -109,70 +355,57 @@ pub(crate) fn extract_found_calendars(xml_bytes: Vec<u8>) -> Vec<Calendar> {
     let envelope_n = xot.add_name_ns("Envelope", soap_ns);
     let body_n = xot.add_name_ns("Body", soap_ns);
     let find_folder_resp_n = xot.add_name_ns("FindFolderResponse", m_ns);
+    let get_folder_resp_n = xot.add_name_ns("GetFolderResponse", m_ns);
     let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
-    let find_folder_resp_msg_n = xot.add_name_ns("FindFolderResponseMessage", m_ns);
+    let resp_code_n = xot.add_name_ns("ResponseCode", m_ns);
     let root_folder_n = xot.add_name_ns("RootFolder", m_ns);
-    let folders_n = xot.add_name_ns("Folders", t_ns);
+    let m_folders_n = xot.add_name_ns("Folders", m_ns);
+    let t_folders_n = xot.add_name_ns("Folders", t_ns);
     let calendar_folder_n = xot.add_name_ns("CalendarFolder", t_ns);
-    let folder_id_n = xot.add_name_ns("FolderId", t_ns);
-    let display_name_n = xot.add_name_ns("DisplayName", t_ns);
-    let id_n = xot.add_name("Id");
-    let change_key_n = xot.add_name("ChangeKey");
+    let resp_class_n = xot.add_name("ResponseClass");
 
-    let calendar_nodes: Vec<Node> = doc
+    let body = doc
         .first_child_element_named(&xot, envelope_n)
         .expect("no soap:Envelope")
         .first_child_element_named(&xot, body_n)
-        .expect("no soap:Body")
-        .first_child_element_named(&xot, find_folder_resp_n)
-        .expect("no m:FindFolderResponse")
+        .expect("no soap:Body");
+    let response = body.first_child_element_named(&xot, find_folder_resp_n)
+        .or_else(|| body.first_child_element_named(&xot, get_folder_resp_n))
+        .expect("no m:FindFolderResponse or m:GetFolderResponse");
+    let response_message = response
         .first_child_element_named(&xot, resp_msgs_n)
         .expect("no m:ResponseMessages")
-        .first_child_element_named(&xot, find_folder_resp_msg_n)
-        .expect("no m:FindFolderResponseMessage")
-        .first_child_element_named(&xot, root_folder_n)
-        .expect("no m:RootFolder")
-        .first_child_element_named(&xot, folders_n)
-        .expect("no t:Folders")
-        .child_elements_named(&xot, calendar_folder_n);
-
-    let mut calendars = Vec::new();
-    for calendar_node in calendar_nodes {
-        let folder_id_elem = calendar_node
-            .children(&xot).into_iter()
-            .filter(|c| xot.is_element_named(*c, folder_id_n))
-            .nth(0).expect("no t:FolderId");
-        let folder_id = xot.get_attribute(folder_id_elem, id_n)
-            .expect("no Id attribute");
-        let change_key = xot.get_attribute(folder_id_elem, change_key_n)
-            .expect("no ChangeKey attribute");
-
-        let display_name_text = calendar_node
-            .children(&xot).into_iter()
-            .filter(|c| xot.is_element_named(*c, display_name_n))
-            .nth(0).expect("no t:DisplayName")
-            .children(&xot).into_iter()
-            .filter(|c| xot.is_text(*c))
-            .nth(0).expect("no text node");
-        let display_name_str = xot.text_str(display_name_text).unwrap();
-
-        let folder_id_obj = FolderId::new(
-            folder_id.to_owned(),
-            change_key.to_owned(),
-        );
+        .children(&xot).into_iter()
+        .find(|c| xot.element(*c).is_some())
+        .expect("no response message");
 
-        calendars.push(Calendar::new(
-            folder_id_obj,
-            display_name_str.to_owned(),
-        ));
+    let resp_class = xot.get_attribute(response_message, resp_class_n).unwrap();
+    if resp_class != "Success" {
+        let code_string = response_message
+            .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
+            .child_text(&xot).expect("m:ResponseCode does not only have text children");
+        return Err(code_string);
     }
 
-    calendars.sort_unstable_by_key(|c| c.display_name.clone());
+    let folders_elem = match response_message.first_child_element_named(&xot, root_folder_n) {
+        Some(root_folder) => root_folder
+            .first_child_element_named(&xot, t_folders_n)
+            .expect("no t:Folders"),
+        None => response_message
+            .first_child_element_named(&xot, m_folders_n)
+            .expect("no m:RootFolder or m:Folders"),
+    };
 
-    calendars
+    let calendar_nodes = folders_elem.child_elements_named(&xot, calendar_folder_n);
+    let mut calendars = Vec::with_capacity(calendar_nodes.len());
+    for calendar_node in calendar_nodes {
+        calendars.push(calendar_from_node(&mut xot, calendar_node, t_ns, owner));
+    }
+    Ok(calendars)
 }
 
-pub(crate) fn create_event(event: &NewEvent, folder_id: &FolderId) -> Vec<u8> {
+/// Creates an appointment, tagging it with the given batch ID.
+pub(crate) fn create_event(event: &NewEvent, folder_id: &FolderId, batch_id: &str) -> Vec<u8> {
     let mut xot = Xot::new();
     let soap_doc = xot.create_exchange_soap_doc(true);
 
@@ -182,8 +415,14 @@ pub(crate) fn create_event(event: &NewEvent, folder_id: &FolderId) -> Vec<u8> {
     xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
 
     let create_item = xot.create_element_ns(soap_doc.m_ns, "CreateItem");
-    // the following attribute ensures that an appointment and not a meeting is created:
-    xot.set_attribute_value(create_item, "SendMeetingInvitations", "SendToNone");
+    // without attendees, the following attribute ensures that an appointment and not a meeting is
+    // created; with attendees, it sends out the invitations
+    let send_invitations = if event.attendees.is_empty() {
+        "SendToNone"
+    } else {
+        "SendToAllAndSaveCopy"
+    };
+    xot.set_attribute_value(create_item, "SendMeetingInvitations", send_invitations);
     xot.append(soap_doc.soap_body, create_item).unwrap();
 
     let target_folder_id = xot.create_element_ns(soap_doc.m_ns, "SavedItemFolderId");
@@ -200,35 +439,75 @@ pub(crate) fn create_event(event: &NewEvent, folder_id: &FolderId) -> Vec<u8> {
     let calendar_item = xot.create_element_ns(soap_doc.t_ns, "CalendarItem");
     xot.append(items, calendar_item).unwrap();
 
+    // the order of the elements is prescribed by the schema
+
     let subject = xot.create_text_element_ns(soap_doc.t_ns, "Subject", &event.title);
     xot.append(calendar_item, subject).unwrap();
 
-    if let Some(loc) = &event.location {
-        let location = xot.create_text_element_ns(soap_doc.t_ns, "Location", loc);
-        xot.append(calendar_item, location).unwrap();
+    let sensitivity = xot.create_text_element_ns(soap_doc.t_ns, "Sensitivity", event.sensitivity.as_exchange_str());
+    xot.append(calendar_item, sensitivity).unwrap();
+
+    if let Some(event_body) = &event.body {
+        let body = xot.create_text_element_ns(soap_doc.t_ns, "Body", &event_body.content);
+        xot.set_attribute_value(body, "BodyType", event_body.body_type.as_exchange_str());
+        xot.append(calendar_item, body).unwrap();
+    }
+
+    if !event.categories.is_empty() {
+        let categories = xot.create_element_ns(soap_doc.t_ns, "Categories");
+        for category in &event.categories {
+            let category_string = xot.create_text_element_ns(soap_doc.t_ns, "String", category);
+            xot.append(categories, category_string).unwrap();
+        }
+        xot.append(calendar_item, categories).unwrap();
     }
 
-    let reminder_is_set = xot.create_text_element_ns(soap_doc.t_ns, "ReminderIsSet", "false");
+    let importance = xot.create_text_element_ns(soap_doc.t_ns, "Importance", event.importance.as_exchange_str());
+    xot.append(calendar_item, importance).unwrap();
+
+    let reminder_is_set = xot.create_text_element_ns(
+        soap_doc.t_ns,
+        "ReminderIsSet",
+        if event.reminder_minutes.is_some() { "true" } else { "false" },
+    );
     xot.append(calendar_item, reminder_is_set).unwrap();
 
+    if let Some(minutes) = event.reminder_minutes {
+        let reminder_minutes = xot.create_text_element_ns(
+            soap_doc.t_ns,
+            "ReminderMinutesBeforeStart",
+            &minutes.to_string(),
+        );
+        xot.append(calendar_item, reminder_minutes).unwrap();
+    }
+
+    // tag the event so that it can be found again later (see find_tagged_items)
+    let tool_prop = create_tag_property(&mut xot, soap_doc.t_ns, TOOL_PROPERTY_NAME, TOOL_NAME);
+    xot.append(calendar_item, tool_prop).unwrap();
+    let batch_prop = create_tag_property(&mut xot, soap_doc.t_ns, BATCH_PROPERTY_NAME, batch_id);
+    xot.append(calendar_item, batch_prop).unwrap();
+
+    // all-day events span from the start of their first day to the start of the day after
+    let (start_time, end_time) = event.time.utc_bounds();
+
     let start = xot.create_text_element_ns(
         soap_doc.t_ns,
         "Start",
-        &event.start_time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        &start_time.format(EXCHANGE_TIMESTAMP_FORMAT).to_string(),
     );
     xot.append(calendar_item, start).unwrap();
 
     let end = xot.create_text_element_ns(
         soap_doc.t_ns,
         "End",
-        &event.end_time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        &end_time.format(EXCHANGE_TIMESTAMP_FORMAT).to_string(),
     );
     xot.append(calendar_item, end).unwrap();
 
     let is_all_day = xot.create_text_element_ns(
         soap_doc.t_ns,
         "IsAllDayEvent",
-        "false",
+        if event.time.is_all_day() { "true" } else { "false" },
     );
     xot.append(calendar_item, is_all_day).unwrap();
 
@@ -239,51 +518,849 @@ pub(crate) fn create_event(event: &NewEvent, folder_id: &FolderId) -> Vec<u8> {
     );
     xot.append(calendar_item, legacy_free_busy).unwrap();
 
+    if let Some(loc) = &event.location {
+        let location = xot.create_text_element_ns(soap_doc.t_ns, "Location", loc);
+        xot.append(calendar_item, location).unwrap();
+    }
+
+    let attendee_lists = [
+        (AttendeeKind::Required, "RequiredAttendees"),
+        (AttendeeKind::Optional, "OptionalAttendees"),
+        (AttendeeKind::Resource, "Resources"),
+    ];
+    for (kind, list_name) in attendee_lists {
+        let mut attendees = event.attendees.iter()
+            .filter(|a| a.kind == kind)
+            .peekable();
+        if attendees.peek().is_none() {
+            continue;
+        }
+
+        let list = xot.create_element_ns(soap_doc.t_ns, list_name);
+        for attendee in attendees {
+            let attendee_elem = xot.create_element_ns(soap_doc.t_ns, "Attendee");
+            xot.append(list, attendee_elem).unwrap();
+
+            let mailbox = xot.create_element_ns(soap_doc.t_ns, "Mailbox");
+            xot.append(attendee_elem, mailbox).unwrap();
+
+            if let Some(name) = &attendee.mailbox.name {
+                let name_elem = xot.create_text_element_ns(soap_doc.t_ns, "Name", name);
+                xot.append(mailbox, name_elem).unwrap();
+            }
+
+            let email_elem = xot.create_text_element_ns(soap_doc.t_ns, "EmailAddress", &attendee.mailbox.email_address);
+            xot.append(mailbox, email_elem).unwrap();
+        }
+        xot.append(calendar_item, list).unwrap();
+    }
+
+    if let Some(recurrence) = &event.recurrence {
+        let recurrence_elem = create_recurrence(&mut xot, soap_doc.t_ns, recurrence);
+        xot.append(calendar_item, recurrence_elem).unwrap();
+    }
+
+    // the instants are sent in UTC; the time zones make Outlook display the event (and expand its
+    // recurrences) in the zone in which it was entered
+    let time_zones = [
+        ("StartTimeZone", event.time.start_time_zone()),
+        ("EndTimeZone", event.time.end_time_zone()),
+    ];
+    for (element_name, tz) in time_zones {
+        if let Some(windows_zone) = windows_zone_id(tz) {
+            let time_zone_elem = xot.create_element_ns(soap_doc.t_ns, element_name);
+            xot.set_attribute_value(time_zone_elem, "Id", windows_zone);
+            xot.append(calendar_item, time_zone_elem).unwrap();
+        }
+    }
+
+    let mut buf = Vec::new();
+    xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
+        .expect("failed to serialize XML");
+    buf
+}
+
+/// Finds the items in the given folder that were created by this tool.
+///
+/// If a batch ID is given, only the items created in that batch are found.
+pub(crate) fn find_tagged_items(folder_id: &FolderId, batch_id: Option<&str>, offset: usize) -> Vec<u8> {
+    let mut xot = Xot::new();
+    let soap_doc = xot.create_exchange_soap_doc(true);
+
+    let req_version = xot.create_element_ns(soap_doc.t_ns, "RequestServerVersion");
+    xot.set_attribute_value(req_version, "Version", "Exchange2016");
+    xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
+
+    let find_item = xot.create_element_ns(soap_doc.m_ns, "FindItem");
+    xot.set_attribute_value(find_item, "Traversal", "Shallow");
+    xot.append(soap_doc.soap_body, find_item).unwrap();
+
+    append_calendar_item_shape(&mut xot, soap_doc.m_ns, soap_doc.t_ns, find_item);
+
+    let page_view = xot.create_element_ns(soap_doc.m_ns, "IndexedPageItemView");
+    xot.set_attribute_value(page_view, "MaxEntriesReturned", &FIND_ITEM_PAGE_SIZE.to_string());
+    xot.set_attribute_value(page_view, "Offset", &offset.to_string());
+    xot.set_attribute_value(page_view, "BasePoint", "Beginning");
+    xot.append(find_item, page_view).unwrap();
+
+    let restriction = xot.create_element_ns(soap_doc.m_ns, "Restriction");
+    xot.append(find_item, restriction).unwrap();
+
+    let (property_name, value) = match batch_id {
+        Some(bid) => (BATCH_PROPERTY_NAME, bid),
+        None => (TOOL_PROPERTY_NAME, TOOL_NAME),
+    };
+
+    let equals = xot.create_element_ns(soap_doc.t_ns, "IsEqualTo");
+    xot.append(restriction, equals).unwrap();
+
+    let ext_field_uri = create_tag_field_uri(&mut xot, soap_doc.t_ns, property_name);
+    xot.append(equals, ext_field_uri).unwrap();
+
+    let fuoc = xot.create_element_ns(soap_doc.t_ns, "FieldURIOrConstant");
+    xot.append(equals, fuoc).unwrap();
+
+    let constant = xot.create_element_ns(soap_doc.t_ns, "Constant");
+    xot.set_attribute_value(constant, "Value", value);
+    xot.append(fuoc, constant).unwrap();
+
+    let parent_folder_ids = xot.create_element_ns(soap_doc.m_ns, "ParentFolderIds");
+    xot.append(find_item, parent_folder_ids).unwrap();
+
+    let folder_id_elem = xot.create_element_ns(soap_doc.t_ns, "FolderId");
+    xot.set_attribute_value(folder_id_elem, "Id", &folder_id.id);
+    xot.set_attribute_value(folder_id_elem, "ChangeKey", &folder_id.change_key);
+    xot.append(parent_folder_ids, folder_id_elem).unwrap();
+
+    let mut buf = Vec::new();
+    xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
+        .expect("failed to serialize XML");
+    buf
+}
+
+/// Appends an `m:ItemShape` requesting the properties required to populate an [`ExistingEvent`].
+fn append_calendar_item_shape(xot: &mut Xot, m_ns: NamespaceId, t_ns: NamespaceId, parent: Node) {
+    let item_shape = xot.create_element_ns(m_ns, "ItemShape");
+    xot.append(parent, item_shape).unwrap();
+
+    let base_shape = xot.create_text_element_ns(t_ns, "BaseShape", "IdOnly");
+    xot.append(item_shape, base_shape).unwrap();
+
+    let add_props = xot.create_element_ns(t_ns, "AdditionalProperties");
+    xot.append(item_shape, add_props).unwrap();
+
+    for field in ["item:Subject", "calendar:Start", "calendar:End", "calendar:Location", "calendar:LegacyFreeBusyStatus", "calendar:CalendarItemType"] {
+        let field_uri = xot.create_element_ns(t_ns, "FieldURI");
+        xot.set_attribute_value(field_uri, "FieldURI", field);
+        xot.append(add_props, field_uri).unwrap();
+    }
+}
+
+fn parse_exchange_timestamp(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .expect("failed to parse Exchange timestamp")
+        .with_timezone(&Utc)
+}
+
+/// Converts a t:CalendarItem element with the properties requested by
+/// [`append_calendar_item_shape`] into an existing event.
+fn existing_event_from_node(xot: &mut Xot, calendar_node: Node, t_ns: NamespaceId) -> ExistingEvent {
+    let item_id_n = xot.add_name_ns("ItemId", t_ns);
+    let subject_n = xot.add_name_ns("Subject", t_ns);
+    let start_n = xot.add_name_ns("Start", t_ns);
+    let end_n = xot.add_name_ns("End", t_ns);
+    let location_n = xot.add_name_ns("Location", t_ns);
+    let free_busy_n = xot.add_name_ns("LegacyFreeBusyStatus", t_ns);
+    let item_type_n = xot.add_name_ns("CalendarItemType", t_ns);
+    let id_n = xot.add_name("Id");
+    let change_key_n = xot.add_name("ChangeKey");
+    let xot: &Xot = xot;
+
+    let item_id_elem = calendar_node
+        .first_child_element_named(xot, item_id_n)
+        .expect("no t:ItemId");
+    let id = xot.get_attribute(item_id_elem, id_n)
+        .expect("no Id attribute");
+    let change_key = xot.get_attribute(item_id_elem, change_key_n)
+        .expect("no ChangeKey attribute");
+    let item_id = ItemId::new(id.to_owned(), change_key.to_owned());
+
+    let child_text = |name| calendar_node
+        .first_child_element_named(xot, name)
+        .map(|n| n.child_text(xot).expect("property does not only have text children"));
+
+    let start_time = parse_exchange_timestamp(&child_text(start_n).expect("no t:Start"));
+    let end_time = parse_exchange_timestamp(&child_text(end_n).expect("no t:End"));
+    let title = child_text(subject_n).unwrap_or_default();
+    let location = child_text(location_n)
+        .filter(|l| !l.is_empty());
+    let free_busy_status = child_text(free_busy_n)
+        .and_then(|fbs| FreeBusyStatus::from_exchange_str(&fbs))
+        .unwrap_or_default();
+    let item_type = child_text(item_type_n)
+        .and_then(|it| CalendarItemType::from_exchange_str(&it))
+        .unwrap_or_default();
+
+    ExistingEvent::new(
+        item_id,
+        start_time,
+        end_time,
+        title,
+        location,
+        free_busy_status,
+        item_type,
+    )
+}
+
+/// Obtains a calendar item, or the recurring master of an occurrence if `recurring_master` is
+/// set.
+fn get_calendar_item(item_id: &ItemId, recurring_master: bool) -> Vec<u8> {
+    let mut xot = Xot::new();
+    let soap_doc = xot.create_exchange_soap_doc(true);
+
+    let req_version = xot.create_element_ns(soap_doc.t_ns, "RequestServerVersion");
+    xot.set_attribute_value(req_version, "Version", "Exchange2016");
+    xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
+
+    let get_item = xot.create_element_ns(soap_doc.m_ns, "GetItem");
+    xot.append(soap_doc.soap_body, get_item).unwrap();
+
+    append_calendar_item_shape(&mut xot, soap_doc.m_ns, soap_doc.t_ns, get_item);
+
+    let item_ids_elem = xot.create_element_ns(soap_doc.m_ns, "ItemIds");
+    xot.append(get_item, item_ids_elem).unwrap();
+
+    // the change key is omitted so that the current version is returned
+    let item_id_elem = if recurring_master {
+        let elem = xot.create_element_ns(soap_doc.t_ns, "RecurringMasterItemId");
+        xot.set_attribute_value(elem, "OccurrenceId", &item_id.id);
+        elem
+    } else {
+        let elem = xot.create_element_ns(soap_doc.t_ns, "ItemId");
+        xot.set_attribute_value(elem, "Id", &item_id.id);
+        elem
+    };
+    xot.append(item_ids_elem, item_id_elem).unwrap();
+
     let mut buf = Vec::new();
     xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
         .expect("failed to serialize XML");
     buf
 }
 
-pub(crate) fn extract_success(xml_bytes: Vec<u8>) {
+/// Obtains the current state (including the current change key) of a calendar item.
+pub(crate) fn get_event(item_id: &ItemId) -> Vec<u8> {
+    get_calendar_item(item_id, false)
+}
+
+/// Obtains the recurring master of the series to which the occurrence belongs.
+pub(crate) fn get_recurring_master(occurrence_id: &ItemId) -> Vec<u8> {
+    get_calendar_item(occurrence_id, true)
+}
+
+/// Extracts the event from a GetItem response, or the response code if the request failed.
+pub(crate) fn extract_got_event(xml_bytes: Vec<u8>) -> Result<ExistingEvent, String> {
     let mut xot = Xot::new();
     let doc = xot.parse_bytes(&xml_bytes)
         .expect("failed to parse XML");
 
     let soap_ns = xot.namespace(SOAP_NS_URI).unwrap();
     let m_ns = xot.namespace(EXCHANGE_MESSAGES_NS_URI).unwrap();
+    let t_ns = xot.namespace(EXCHANGE_TYPES_NS_URI).unwrap();
 
     let envelope_n = xot.add_name_ns("Envelope", soap_ns);
     let body_n = xot.add_name_ns("Body", soap_ns);
-    let cir_n = xot.add_name_ns("CreateItemResponse", m_ns);
+    let gir_n = xot.add_name_ns("GetItemResponse", m_ns);
     let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
-    let cirm_n = xot.add_name_ns("CreateItemResponseMessage", m_ns);
+    let girm_n = xot.add_name_ns("GetItemResponseMessage", m_ns);
     let resp_code_n = xot.add_name_ns("ResponseCode", m_ns);
+    let items_n = xot.add_name_ns("Items", m_ns);
+    let calendar_item_n = xot.add_name_ns("CalendarItem", t_ns);
     let resp_class_n = xot.add_name("ResponseClass");
 
-    let response_nodes: Vec<Node> = doc
+    let response_node = doc
         .first_child_element_named(&xot, envelope_n)
         .expect("no soap:Envelope")
         .first_child_element_named(&xot, body_n)
         .expect("no soap:Body")
-        .first_child_element_named(&xot, cir_n)
-        .expect("no m:CreateItemResponse")
+        .first_child_element_named(&xot, gir_n)
+        .expect("no m:GetItemResponse")
         .first_child_element_named(&xot, resp_msgs_n)
         .expect("no m:ResponseMessages")
-        .children(&xot).into_iter()
-        .filter(|c| xot.is_element_named(*c, cirm_n))
-        .collect();
+        .first_child_element_named(&xot, girm_n)
+        .expect("no m:GetItemResponseMessage");
 
-    for response_node in response_nodes {
-        let code_string = response_node
-            .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
-            .child_text(&xot).expect("m:ResponseCode does not only have text children");
+    let code_string = response_node
+        .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
+        .child_text(&xot).expect("m:ResponseCode does not only have text children");
+    let resp_class = xot.get_attribute(response_node, resp_class_n).unwrap();
+    if resp_class != "Success" || code_string != "NoError" {
+        return Err(code_string);
+    }
 
-        let resp_class = xot.get_attribute(response_node, resp_class_n).unwrap();
-        if resp_class != "Success" || code_string != "NoError" {
-            println!("response class: {}, response code: {}", resp_class, code_string);
-            println!("{:?}", std::str::from_utf8(&xml_bytes));
-        }
-        return;
+    let calendar_node = response_node
+        .first_child_element_named(&xot, items_n)
+        .expect("no m:Items")
+        .first_child_element_named(&xot, calendar_item_n)
+        .ok_or_else(|| "ErrorItemNotCalendarItem".to_owned())?;
+    Ok(existing_event_from_node(&mut xot, calendar_node, t_ns))
+}
+
+/// Extracts the calendar items from a FindItem response.
+///
+/// Returns whether the last item in the range has been returned and the items themselves.
+pub(crate) fn extract_found_items(xml_bytes: Vec<u8>) -> (bool, Vec<ExistingEvent>) {
+    let mut xot = Xot::new();
+    let doc = xot.parse_bytes(&xml_bytes)
+        .expect("failed to parse XML");
+
+    let soap_ns = xot.namespace(SOAP_NS_URI).unwrap();
+    let m_ns = xot.namespace(EXCHANGE_MESSAGES_NS_URI).unwrap();
+    let t_ns = xot.namespace(EXCHANGE_TYPES_NS_URI).unwrap();
+
+    let envelope_n = xot.add_name_ns("Envelope", soap_ns);
+    let body_n = xot.add_name_ns("Body", soap_ns);
+    let find_item_resp_n = xot.add_name_ns("FindItemResponse", m_ns);
+    let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
+    let find_item_resp_msg_n = xot.add_name_ns("FindItemResponseMessage", m_ns);
+    let resp_code_n = xot.add_name_ns("ResponseCode", m_ns);
+    let root_folder_n = xot.add_name_ns("RootFolder", m_ns);
+    let items_n = xot.add_name_ns("Items", t_ns);
+    let calendar_item_n = xot.add_name_ns("CalendarItem", t_ns);
+    let resp_class_n = xot.add_name("ResponseClass");
+    let includes_last_n = xot.add_name("IncludesLastItemInRange");
+
+    let response_node = doc
+        .first_child_element_named(&xot, envelope_n)
+        .expect("no soap:Envelope")
+        .first_child_element_named(&xot, body_n)
+        .expect("no soap:Body")
+        .first_child_element_named(&xot, find_item_resp_n)
+        .expect("no m:FindItemResponse")
+        .first_child_element_named(&xot, resp_msgs_n)
+        .expect("no m:ResponseMessages")
+        .first_child_element_named(&xot, find_item_resp_msg_n)
+        .expect("no m:FindItemResponseMessage");
+
+    let resp_class = xot.get_attribute(response_node, resp_class_n).unwrap();
+    if resp_class != "Success" {
+        let code_string = response_node
+            .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
+            .child_text(&xot).expect("m:ResponseCode does not only have text children");
+        println!("response class: {}, response code: {}", resp_class, code_string);
+        println!("{:?}", std::str::from_utf8(&xml_bytes));
+        return (true, Vec::new());
+    }
+
+    let root_folder = response_node
+        .first_child_element_named(&xot, root_folder_n)
+        .expect("no m:RootFolder");
+    let is_last = xot.get_attribute(root_folder, includes_last_n)
+        .map(|v| v == "true")
+        .unwrap_or(true);
+
+    let calendar_nodes: Vec<Node> = root_folder
+        .first_child_element_named(&xot, items_n)
+        .expect("no t:Items")
+        .child_elements_named(&xot, calendar_item_n);
+
+    let mut events = Vec::with_capacity(calendar_nodes.len());
+    for calendar_node in calendar_nodes {
+        events.push(existing_event_from_node(&mut xot, calendar_node, t_ns));
+    }
+
+    (is_last, events)
+}
+
+pub(crate) fn extract_success(xml_bytes: Vec<u8>) -> Vec<ItemId> {
+    let mut xot = Xot::new();
+    let doc = xot.parse_bytes(&xml_bytes)
+        .expect("failed to parse XML");
+
+    let soap_ns = xot.namespace(SOAP_NS_URI).unwrap();
+    let m_ns = xot.namespace(EXCHANGE_MESSAGES_NS_URI).unwrap();
+    let t_ns = xot.namespace(EXCHANGE_TYPES_NS_URI).unwrap();
+
+    let envelope_n = xot.add_name_ns("Envelope", soap_ns);
+    let body_n = xot.add_name_ns("Body", soap_ns);
+    let cir_n = xot.add_name_ns("CreateItemResponse", m_ns);
+    let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
+    let cirm_n = xot.add_name_ns("CreateItemResponseMessage", m_ns);
+    let resp_code_n = xot.add_name_ns("ResponseCode", m_ns);
+    let items_n = xot.add_name_ns("Items", m_ns);
+    let item_id_n = xot.add_name_ns("ItemId", t_ns);
+    let resp_class_n = xot.add_name("ResponseClass");
+    let id_n = xot.add_name("Id");
+    let change_key_n = xot.add_name("ChangeKey");
+
+    let response_nodes: Vec<Node> = doc
+        .first_child_element_named(&xot, envelope_n)
+        .expect("no soap:Envelope")
+        .first_child_element_named(&xot, body_n)
+        .expect("no soap:Body")
+        .first_child_element_named(&xot, cir_n)
+        .expect("no m:CreateItemResponse")
+        .first_child_element_named(&xot, resp_msgs_n)
+        .expect("no m:ResponseMessages")
+        .children(&xot).into_iter()
+        .filter(|c| xot.is_element_named(*c, cirm_n))
+        .collect();
+
+    let mut item_ids = Vec::new();
+    for response_node in response_nodes {
+        let code_string = response_node
+            .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
+            .child_text(&xot).expect("m:ResponseCode does not only have text children");
+
+        let resp_class = xot.get_attribute(response_node, resp_class_n).unwrap();
+        if resp_class != "Success" || code_string != "NoError" {
+            println!("response class: {}, response code: {}", resp_class, code_string);
+            println!("{:?}", std::str::from_utf8(&xml_bytes));
+            continue;
+        }
+
+        // m:Items contains the created item (e.g. t:CalendarItem), which in turn contains its t:ItemId
+        let Some(items_node) = response_node.first_child_element_named(&xot, items_n)
+            else { continue };
+        let item_nodes: Vec<Node> = items_node
+            .children(&xot).into_iter()
+            .filter(|c| xot.is_element(*c))
+            .collect();
+        for item_node in item_nodes {
+            let item_id_elem = item_node
+                .first_child_element_named(&xot, item_id_n)
+                .expect("created item without t:ItemId");
+            let id = xot.get_attribute(item_id_elem, id_n)
+                .expect("no Id attribute");
+            let change_key = xot.get_attribute(item_id_elem, change_key_n)
+                .expect("no ChangeKey attribute");
+            item_ids.push(ItemId::new(
+                id.to_owned(),
+                change_key.to_owned(),
+            ));
+        }
+    }
+    item_ids
+}
+
+/// Appends a t:SetItemField update setting the given field of a calendar item to a text value.
+fn append_set_calendar_field(xot: &mut Xot, t_ns: NamespaceId, updates: Node, field_uri: &str, element_name: &str, value: &str) -> Node {
+    let set_field = xot.create_element_ns(t_ns, "SetItemField");
+    xot.append(updates, set_field).unwrap();
+
+    let field_uri_elem = xot.create_element_ns(t_ns, "FieldURI");
+    xot.set_attribute_value(field_uri_elem, "FieldURI", field_uri);
+    xot.append(set_field, field_uri_elem).unwrap();
+
+    let calendar_item = xot.create_element_ns(t_ns, "CalendarItem");
+    xot.append(set_field, calendar_item).unwrap();
+
+    let value_elem = xot.create_text_element_ns(t_ns, element_name, value);
+    xot.append(calendar_item, value_elem).unwrap();
+    value_elem
+}
+
+/// Appends a t:DeleteItemField update clearing the given field.
+fn append_delete_field(xot: &mut Xot, t_ns: NamespaceId, updates: Node, field_uri: &str) {
+    let delete_field = xot.create_element_ns(t_ns, "DeleteItemField");
+    xot.append(updates, delete_field).unwrap();
+
+    let field_uri_elem = xot.create_element_ns(t_ns, "FieldURI");
+    xot.set_attribute_value(field_uri_elem, "FieldURI", field_uri);
+    xot.append(delete_field, field_uri_elem).unwrap();
+}
+
+/// Changes an existing calendar item. Attendees of meetings are informed of the changes.
+pub(crate) fn update_event(item_id: &ItemId, changes: &EventChanges, conflict_resolution: ConflictResolution) -> Vec<u8> {
+    let mut xot = Xot::new();
+    let soap_doc = xot.create_exchange_soap_doc(true);
+
+    let req_version = xot.create_element_ns(soap_doc.t_ns, "RequestServerVersion");
+    xot.set_attribute_value(req_version, "Version", "Exchange2016");
+    xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
+
+    let update_item = xot.create_element_ns(soap_doc.m_ns, "UpdateItem");
+    xot.set_attribute_value(update_item, "ConflictResolution", conflict_resolution.as_exchange_str());
+    // mandatory when updating calendar items; updates are only sent for meetings we organize
+    xot.set_attribute_value(update_item, "SendMeetingInvitationsOrCancellations", "SendToChangedAndSaveCopy");
+    xot.append(soap_doc.soap_body, update_item).unwrap();
+
+    let item_changes = xot.create_element_ns(soap_doc.m_ns, "ItemChanges");
+    xot.append(update_item, item_changes).unwrap();
+
+    let item_change = xot.create_element_ns(soap_doc.t_ns, "ItemChange");
+    xot.append(item_changes, item_change).unwrap();
+
+    let item_id_elem = xot.create_element_ns(soap_doc.t_ns, "ItemId");
+    xot.set_attribute_value(item_id_elem, "Id", &item_id.id);
+    xot.set_attribute_value(item_id_elem, "ChangeKey", &item_id.change_key);
+    xot.append(item_change, item_id_elem).unwrap();
+
+    let updates = xot.create_element_ns(soap_doc.t_ns, "Updates");
+    xot.append(item_change, updates).unwrap();
+
+    if let Some(title) = &changes.title {
+        append_set_calendar_field(&mut xot, soap_doc.t_ns, updates, "item:Subject", "Subject", title);
+    }
+    if let Some(start_time) = &changes.start_time {
+        let start_str = start_time.format(EXCHANGE_TIMESTAMP_FORMAT).to_string();
+        append_set_calendar_field(&mut xot, soap_doc.t_ns, updates, "calendar:Start", "Start", &start_str);
+    }
+    if let Some(end_time) = &changes.end_time {
+        let end_str = end_time.format(EXCHANGE_TIMESTAMP_FORMAT).to_string();
+        append_set_calendar_field(&mut xot, soap_doc.t_ns, updates, "calendar:End", "End", &end_str);
+    }
+    if let Some(free_busy_status) = &changes.free_busy_status {
+        append_set_calendar_field(&mut xot, soap_doc.t_ns, updates, "calendar:LegacyFreeBusyStatus", "LegacyFreeBusyStatus", free_busy_status.as_exchange_str());
+    }
+    match &changes.location {
+        None => {},
+        Some(Some(location)) => {
+            append_set_calendar_field(&mut xot, soap_doc.t_ns, updates, "calendar:Location", "Location", location);
+        },
+        Some(None) => append_delete_field(&mut xot, soap_doc.t_ns, updates, "calendar:Location"),
+    }
+    if let Some(event_body) = &changes.body {
+        let body = append_set_calendar_field(&mut xot, soap_doc.t_ns, updates, "item:Body", "Body", &event_body.content);
+        xot.set_attribute_value(body, "BodyType", event_body.body_type.as_exchange_str());
+    }
+
+    let mut buf = Vec::new();
+    xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
+        .expect("failed to serialize XML");
+    buf
+}
+
+/// Extracts the outcome of each change from an UpdateItem response: the updated item ID (with its
+/// new change key) or the response code.
+pub(crate) fn extract_update_success(xml_bytes: Vec<u8>) -> Vec<Result<ItemId, String>> {
+    let mut xot = Xot::new();
+    let doc = xot.parse_bytes(&xml_bytes)
+        .expect("failed to parse XML");
+
+    let soap_ns = xot.namespace(SOAP_NS_URI).unwrap();
+    let m_ns = xot.namespace(EXCHANGE_MESSAGES_NS_URI).unwrap();
+    let t_ns = xot.namespace(EXCHANGE_TYPES_NS_URI).unwrap();
+
+    let envelope_n = xot.add_name_ns("Envelope", soap_ns);
+    let body_n = xot.add_name_ns("Body", soap_ns);
+    let uir_n = xot.add_name_ns("UpdateItemResponse", m_ns);
+    let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
+    let uirm_n = xot.add_name_ns("UpdateItemResponseMessage", m_ns);
+    let resp_code_n = xot.add_name_ns("ResponseCode", m_ns);
+    let items_n = xot.add_name_ns("Items", m_ns);
+    let item_id_n = xot.add_name_ns("ItemId", t_ns);
+    let resp_class_n = xot.add_name("ResponseClass");
+    let id_n = xot.add_name("Id");
+    let change_key_n = xot.add_name("ChangeKey");
+
+    let response_nodes: Vec<Node> = doc
+        .first_child_element_named(&xot, envelope_n)
+        .expect("no soap:Envelope")
+        .first_child_element_named(&xot, body_n)
+        .expect("no soap:Body")
+        .first_child_element_named(&xot, uir_n)
+        .expect("no m:UpdateItemResponse")
+        .first_child_element_named(&xot, resp_msgs_n)
+        .expect("no m:ResponseMessages")
+        .children(&xot).into_iter()
+        .filter(|c| xot.is_element_named(*c, uirm_n))
+        .collect();
+
+    let mut outcomes = Vec::with_capacity(response_nodes.len());
+    for response_node in response_nodes {
+        let code_string = response_node
+            .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
+            .child_text(&xot).expect("m:ResponseCode does not only have text children");
+
+        let resp_class = xot.get_attribute(response_node, resp_class_n).unwrap();
+        if resp_class != "Success" || code_string != "NoError" {
+            outcomes.push(Err(code_string));
+            continue;
+        }
+
+        // m:Items contains the updated item (e.g. t:CalendarItem), which in turn contains its t:ItemId
+        let item_id_elem = response_node
+            .first_child_element_named(&xot, items_n)
+            .expect("no m:Items")
+            .children(&xot).into_iter()
+            .find(|c| xot.is_element(*c))
+            .expect("no updated item")
+            .first_child_element_named(&xot, item_id_n)
+            .expect("updated item without t:ItemId");
+        let id = xot.get_attribute(item_id_elem, id_n)
+            .expect("no Id attribute");
+        let change_key = xot.get_attribute(item_id_elem, change_key_n)
+            .expect("no ChangeKey attribute");
+        outcomes.push(Ok(ItemId::new(
+            id.to_owned(),
+            change_key.to_owned(),
+        )));
+    }
+    outcomes
+}
+
+pub(crate) fn delete_items(item_ids: &[ItemId], delete_type: DeleteType) -> Vec<u8> {
+    let mut xot = Xot::new();
+    let soap_doc = xot.create_exchange_soap_doc(true);
+
+    let req_version = xot.create_element_ns(soap_doc.t_ns, "RequestServerVersion");
+    xot.set_attribute_value(req_version, "Version", "Exchange2016");
+    xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
+
+    let delete_item = xot.create_element_ns(soap_doc.m_ns, "DeleteItem");
+    xot.set_attribute_value(delete_item, "DeleteType", delete_type.as_exchange_str());
+    // mandatory when deleting calendar items; cancellations are only sent for meetings we organize
+    xot.set_attribute_value(delete_item, "SendMeetingCancellations", "SendToAllAndSaveCopy");
+    xot.append(soap_doc.soap_body, delete_item).unwrap();
+
+    let item_ids_elem = xot.create_element_ns(soap_doc.m_ns, "ItemIds");
+    xot.append(delete_item, item_ids_elem).unwrap();
+
+    for item_id in item_ids {
+        let item_id_elem = xot.create_element_ns(soap_doc.t_ns, "ItemId");
+        xot.set_attribute_value(item_id_elem, "Id", &item_id.id);
+        xot.set_attribute_value(item_id_elem, "ChangeKey", &item_id.change_key);
+        xot.append(item_ids_elem, item_id_elem).unwrap();
+    }
+
+    let mut buf = Vec::new();
+    xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
+        .expect("failed to serialize XML");
+    buf
+}
+
+/// Returns the outcome for each item, in the order in which they were passed to [`delete_items`].
+///
+/// Failures are returned as the Exchange response code.
+pub(crate) fn extract_delete_success(xml_bytes: Vec<u8>) -> Vec<Result<(), String>> {
+    let mut xot = Xot::new();
+    let doc = xot.parse_bytes(&xml_bytes)
+        .expect("failed to parse XML");
+
+    let soap_ns = xot.namespace(SOAP_NS_URI).unwrap();
+    let m_ns = xot.namespace(EXCHANGE_MESSAGES_NS_URI).unwrap();
+
+    let envelope_n = xot.add_name_ns("Envelope", soap_ns);
+    let body_n = xot.add_name_ns("Body", soap_ns);
+    let dir_n = xot.add_name_ns("DeleteItemResponse", m_ns);
+    let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
+    let dirm_n = xot.add_name_ns("DeleteItemResponseMessage", m_ns);
+    let resp_code_n = xot.add_name_ns("ResponseCode", m_ns);
+    let resp_class_n = xot.add_name("ResponseClass");
+
+    let response_nodes: Vec<Node> = doc
+        .first_child_element_named(&xot, envelope_n)
+        .expect("no soap:Envelope")
+        .first_child_element_named(&xot, body_n)
+        .expect("no soap:Body")
+        .first_child_element_named(&xot, dir_n)
+        .expect("no m:DeleteItemResponse")
+        .first_child_element_named(&xot, resp_msgs_n)
+        .expect("no m:ResponseMessages")
+        .children(&xot).into_iter()
+        .filter(|c| xot.is_element_named(*c, dirm_n))
+        .collect();
+
+    let mut outcomes = Vec::with_capacity(response_nodes.len());
+    for response_node in response_nodes {
+        let code_string = response_node
+            .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
+            .child_text(&xot).expect("m:ResponseCode does not only have text children");
+
+        let resp_class = xot.get_attribute(response_node, resp_class_n).unwrap();
+        if resp_class != "Success" || code_string != "NoError" {
+            outcomes.push(Err(code_string));
+        } else {
+            outcomes.push(Ok(()));
+        }
+    }
+    outcomes
+}
+
+/// Copies or moves the given items into the given folder.
+pub(crate) fn transfer_items(item_ids: &[ItemId], folder_id: &FolderId, mode: TransferMode) -> Vec<u8> {
+    let mut xot = Xot::new();
+    let soap_doc = xot.create_exchange_soap_doc(true);
+
+    let req_version = xot.create_element_ns(soap_doc.t_ns, "RequestServerVersion");
+    xot.set_attribute_value(req_version, "Version", "Exchange2016");
+    xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
+
+    let transfer_item = xot.create_element_ns(soap_doc.m_ns, mode.as_exchange_operation());
+    // needed to change the new items afterwards
+    xot.set_attribute_value(transfer_item, "ReturnNewItemIds", "true");
+    xot.append(soap_doc.soap_body, transfer_item).unwrap();
+
+    let to_folder_id = xot.create_element_ns(soap_doc.m_ns, "ToFolderId");
+    xot.append(transfer_item, to_folder_id).unwrap();
+
+    let folder_id_elem = xot.create_element_ns(soap_doc.t_ns, "FolderId");
+    xot.set_attribute_value(folder_id_elem, "Id", &folder_id.id);
+    xot.set_attribute_value(folder_id_elem, "ChangeKey", &folder_id.change_key);
+    xot.append(to_folder_id, folder_id_elem).unwrap();
+
+    let item_ids_elem = xot.create_element_ns(soap_doc.m_ns, "ItemIds");
+    xot.append(transfer_item, item_ids_elem).unwrap();
+
+    for item_id in item_ids {
+        let item_id_elem = xot.create_element_ns(soap_doc.t_ns, "ItemId");
+        xot.set_attribute_value(item_id_elem, "Id", &item_id.id);
+        xot.set_attribute_value(item_id_elem, "ChangeKey", &item_id.change_key);
+        xot.append(item_ids_elem, item_id_elem).unwrap();
+    }
+
+    let mut buf = Vec::new();
+    xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
+        .expect("failed to serialize XML");
+    buf
+}
+
+/// Extracts the outcome of each item from a CopyItem or MoveItem response: the ID of the new item
+/// (if the server returns it, which it does not when the item has been transferred into another
+/// mailbox) or the response code.
+pub(crate) fn extract_transfer_success(xml_bytes: Vec<u8>, mode: TransferMode) -> Vec<Result<Option<ItemId>, String>> {
+    let mut xot = Xot::new();
+    let doc = xot.parse_bytes(&xml_bytes)
+        .expect("failed to parse XML");
+
+    let soap_ns = xot.namespace(SOAP_NS_URI).unwrap();
+    let m_ns = xot.namespace(EXCHANGE_MESSAGES_NS_URI).unwrap();
+    let t_ns = xot.namespace(EXCHANGE_TYPES_NS_URI).unwrap();
+
+    let operation = mode.as_exchange_operation();
+    let envelope_n = xot.add_name_ns("Envelope", soap_ns);
+    let body_n = xot.add_name_ns("Body", soap_ns);
+    let response_n = xot.add_name_ns(&format!("{}Response", operation), m_ns);
+    let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
+    let response_message_n = xot.add_name_ns(&format!("{}ResponseMessage", operation), m_ns);
+    let resp_code_n = xot.add_name_ns("ResponseCode", m_ns);
+    let items_n = xot.add_name_ns("Items", m_ns);
+    let item_id_n = xot.add_name_ns("ItemId", t_ns);
+    let resp_class_n = xot.add_name("ResponseClass");
+    let id_n = xot.add_name("Id");
+    let change_key_n = xot.add_name("ChangeKey");
+
+    let response_nodes: Vec<Node> = doc
+        .first_child_element_named(&xot, envelope_n)
+        .expect("no soap:Envelope")
+        .first_child_element_named(&xot, body_n)
+        .expect("no soap:Body")
+        .first_child_element_named(&xot, response_n)
+        .unwrap_or_else(|| panic!("no m:{}Response", operation))
+        .first_child_element_named(&xot, resp_msgs_n)
+        .expect("no m:ResponseMessages")
+        .children(&xot).into_iter()
+        .filter(|c| xot.is_element_named(*c, response_message_n))
+        .collect();
+
+    let mut outcomes = Vec::with_capacity(response_nodes.len());
+    for response_node in response_nodes {
+        let code_string = response_node
+            .first_child_element_named(&xot, resp_code_n).expect("no m:ResponseCode")
+            .child_text(&xot).expect("m:ResponseCode does not only have text children");
+
+        let resp_class = xot.get_attribute(response_node, resp_class_n).unwrap();
+        if resp_class != "Success" || code_string != "NoError" {
+            outcomes.push(Err(code_string));
+            continue;
+        }
+
+        // m:Items is empty if the new item is in another mailbox
+        let item_id_elem = response_node
+            .first_child_element_named(&xot, items_n)
+            .and_then(|items| items.children(&xot).into_iter().find(|c| xot.is_element(*c)))
+            .and_then(|item| item.first_child_element_named(&xot, item_id_n));
+        let new_item_id = item_id_elem.map(|elem| ItemId::new(
+            xot.get_attribute(elem, id_n).expect("no Id attribute").to_owned(),
+            xot.get_attribute(elem, change_key_n).expect("no ChangeKey attribute").to_owned(),
+        ));
+        outcomes.push(Ok(new_item_id));
+    }
+    outcomes
+}
+
+/// Asks the server to resolve a (partial) name or e-mail address to mailboxes.
+pub(crate) fn resolve_names(unresolved: &str) -> Vec<u8> {
+    let mut xot = Xot::new();
+    let soap_doc = xot.create_exchange_soap_doc(true);
+
+    let req_version = xot.create_element_ns(soap_doc.t_ns, "RequestServerVersion");
+    xot.set_attribute_value(req_version, "Version", "Exchange2016");
+    xot.append(soap_doc.soap_header.unwrap(), req_version).unwrap();
+
+    let resolve_names = xot.create_element_ns(soap_doc.m_ns, "ResolveNames");
+    xot.set_attribute_value(resolve_names, "ReturnFullContactData", "false");
+    xot.set_attribute_value(resolve_names, "SearchScope", "ActiveDirectoryContacts");
+    xot.append(soap_doc.soap_body, resolve_names).unwrap();
+
+    let unresolved_entry = xot.create_text_element_ns(soap_doc.m_ns, "UnresolvedEntry", unresolved);
+    xot.append(resolve_names, unresolved_entry).unwrap();
+
+    let mut buf = Vec::new();
+    xot.serialize_xml_write(Parameters::default(), soap_doc.document, &mut buf)
+        .expect("failed to serialize XML");
+    buf
+}
+
+/// Extracts the mailboxes from a ResolveNames response.
+///
+/// Returns an empty list if the name could not be resolved.
+pub(crate) fn extract_resolved_names(xml_bytes: Vec<u8>) -> Vec<Mailbox> {
+    let mut xot = Xot::new();
+    let doc = xot.parse_bytes(&xml_bytes)
+        .expect("failed to parse XML");
+
+    let soap_ns = xot.namespace(SOAP_NS_URI).unwrap();
+    let m_ns = xot.namespace(EXCHANGE_MESSAGES_NS_URI).unwrap();
+    let t_ns = xot.namespace(EXCHANGE_TYPES_NS_URI).unwrap();
+
+    let envelope_n = xot.add_name_ns("Envelope", soap_ns);
+    let body_n = xot.add_name_ns("Body", soap_ns);
+    let rnr_n = xot.add_name_ns("ResolveNamesResponse", m_ns);
+    let resp_msgs_n = xot.add_name_ns("ResponseMessages", m_ns);
+    let rnrm_n = xot.add_name_ns("ResolveNamesResponseMessage", m_ns);
+    let resolution_set_n = xot.add_name_ns("ResolutionSet", m_ns);
+    let resolution_n = xot.add_name_ns("Resolution", t_ns);
+    let mailbox_n = xot.add_name_ns("Mailbox", t_ns);
+    let name_n = xot.add_name_ns("Name", t_ns);
+    let email_address_n = xot.add_name_ns("EmailAddress", t_ns);
+    let routing_type_n = xot.add_name_ns("RoutingType", t_ns);
+
+    let response_node = doc
+        .first_child_element_named(&xot, envelope_n)
+        .expect("no soap:Envelope")
+        .first_child_element_named(&xot, body_n)
+        .expect("no soap:Body")
+        .first_child_element_named(&xot, rnr_n)
+        .expect("no m:ResolveNamesResponse")
+        .first_child_element_named(&xot, resp_msgs_n)
+        .expect("no m:ResponseMessages")
+        .first_child_element_named(&xot, rnrm_n)
+        .expect("no m:ResolveNamesResponseMessage");
+
+    // multiple results are delivered with a warning, no results with an error (and no resolution set)
+    let Some(resolution_set) = response_node.first_child_element_named(&xot, resolution_set_n)
+        else { return Vec::new() };
+
+    let mut mailboxes = Vec::new();
+    for resolution in resolution_set.child_elements_named(&xot, resolution_n) {
+        let Some(mailbox) = resolution.first_child_element_named(&xot, mailbox_n)
+            else { continue };
+        let child_text = |name| mailbox
+            .first_child_element_named(&xot, name)
+            .and_then(|n| n.child_text(&xot));
+
+        // only SMTP addresses are useful for invitations
+        if child_text(routing_type_n).is_some_and(|rt| rt != "SMTP") {
+            continue;
+        }
+        let Some(email_address) = child_text(email_address_n)
+            else { continue };
+        mailboxes.push(Mailbox::new(
+            child_text(name_n),
+            email_address,
+        ));
     }
+    mailboxes
 }